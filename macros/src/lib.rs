@@ -0,0 +1,721 @@
+//! Procedural macros for `tsukuyomi-router`.
+//!
+//! # Example
+//!
+//! ```
+//! use tsukuyomi_router_macros::routes;
+//!
+//! routes! {
+//!     "/" => index,
+//!     "/users/:id" => user,
+//!     "/static/*" => assets,
+//! }
+//!
+//! assert_eq!(Route::recognize("/"), Some(Route::Index(IndexParams)));
+//! assert_eq!(
+//!     Route::recognize("/users/42"),
+//!     Some(Route::User(UserParams { id: "42" }))
+//! );
+//! assert_eq!(
+//!     Route::recognize("/static/css/style.css"),
+//!     Some(Route::Assets(AssetsParams { wildcard: "css/style.css" }))
+//! );
+//! assert_eq!(Route::recognize("/users"), None);
+//! ```
+
+#![warn(
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_compatibility,
+    rust_2018_idioms,
+    unsafe_code,
+    unused,
+    clippy::unimplemented
+)]
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Ident, LitStr, Token,
+};
+use tsukuyomi_router::Router;
+
+/// Defines a route table whose patterns are validated at compile time.
+///
+/// Each entry has the form `"<pattern>" => <name>`. The patterns use the same
+/// syntax as `Router::add_route`, and a compile error is emitted if a pattern is
+/// invalid, if two patterns are identical except for the parameter names, or if
+/// the route selected for a path matched by two patterns depends on the order of
+/// the entries (e.g. two parameters with overlapping constraints). Other
+/// overlapping patterns are allowed and follow the precedence of `Router`, so
+/// `/users/new` is preferred to `/users/:id`.
+///
+/// The macro expands to the following items:
+///
+/// * For each entry, a struct named `<Name>Params` (the name converted to
+///   UpperCamelCase) with a `&str` field for each parameter, and a field named
+///   `wildcard` if the pattern contains a wildcard.
+/// * An enum `Route` with a variant `<Name>(<Name>Params)` for each entry, and
+///   the associated function `Route::recognize`, which matches the path with
+///   code generated from the route table and selects the same route as `Router`.
+///
+/// The generated code refers to the `tsukuyomi_router` crate, which must be a
+/// dependency of the calling crate.
+#[proc_macro]
+pub fn routes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as RouteTable);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct RouteTable {
+    entries: Punctuated<Entry, Token![,]>,
+}
+
+impl Parse for RouteTable {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        Ok(Self {
+            entries: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+struct Entry {
+    pattern: LitStr,
+    name: Ident,
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let pattern = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let name = input.parse()?;
+        Ok(Self { pattern, name })
+    }
+}
+
+/// A route validated by the runtime parser.
+struct Route {
+    pattern: String,
+    variant: Ident,
+    params_ty: Ident,
+    fields: Vec<Ident>,
+    has_wildcard: bool,
+}
+
+fn validate(entries: &[Entry]) -> syn::Result<Vec<Route>> {
+    let mut router = Router::new();
+    let mut patterns = HashMap::new();
+    let mut variants = HashMap::new();
+    let mut routes = vec![];
+
+    for (i, entry) in entries.iter().enumerate() {
+        let pattern = entry.pattern.value();
+        let id = router.add_route(&pattern, i).map_err(|err| {
+            syn::Error::new(
+                entry.pattern.span(),
+                format!("invalid route pattern: {}", err),
+            )
+        })?;
+
+        if let Some(other) = patterns.insert(normalize(&pattern), i) {
+            return Err(syn::Error::new(
+                entry.pattern.span(),
+                format!(
+                    "the route pattern conflicts with `{}`",
+                    entries[other].pattern.value()
+                ),
+            ));
+        }
+
+        let variant = Ident::new(
+            &to_upper_camel_case(&entry.name.to_string()),
+            entry.name.span(),
+        );
+        if variants.insert(variant.to_string(), i).is_some() {
+            return Err(syn::Error::new(
+                entry.name.span(),
+                format!("the route name `{}` is defined multiple times", variant),
+            ));
+        }
+
+        let endpoint = &router[id];
+        let fields = endpoint
+            .param_names()
+            .map(|name| field_ident(name, entry.pattern.span()))
+            .collect::<Vec<_>>();
+        if endpoint.has_wildcard() && fields.iter().any(|field| field == "wildcard") {
+            return Err(syn::Error::new(
+                entry.pattern.span(),
+                "the parameter name `wildcard` is reserved for the wildcard parameter",
+            ));
+        }
+
+        routes.push(Route {
+            pattern,
+            params_ty: format_ident!("{}Params", variant),
+            variant,
+            fields,
+            has_wildcard: endpoint.has_wildcard(),
+        });
+    }
+
+    // Overlapping patterns are resolved by the precedence rules of the router
+    // (e.g. `/users/new` is preferred to `/users/:id`), so only the conflicts
+    // whose winner changes with the order of the entries are rejected.
+    let mut reversed = Router::new();
+    for (i, route) in routes.iter().enumerate().rev() {
+        reversed
+            .add_route(&route.pattern, i)
+            .expect("the route pattern has been validated");
+    }
+    let mut errors = router
+        .check_conflicts()
+        .into_iter()
+        .filter(|conflict| {
+            let winner = *conflict.winner().data();
+            let reversed_winner = reversed
+                .recognize(conflict.example())
+                .route()
+                .map(|(route, _)| *route.data());
+            reversed_winner != Some(winner)
+        })
+        .map(|conflict| {
+            let (first, second) = conflict.routes();
+            syn::Error::new(
+                entries[*second.data()].pattern.span(),
+                format!(
+                    "the route pattern conflicts with `{}`, since both match {:?} \
+                     and the selected route depends on the order of the entries",
+                    first.path(),
+                    conflict.example()
+                ),
+            )
+        });
+    if let Some(mut error) = errors.next() {
+        error.extend(errors);
+        return Err(error);
+    }
+
+    Ok(routes)
+}
+
+fn expand(input: RouteTable) -> syn::Result<TokenStream> {
+    let entries: Vec<_> = input.entries.into_iter().collect();
+    let routes = validate(&entries)?;
+
+    let any_lifetime = routes
+        .iter()
+        .any(|route| !route.fields.is_empty() || route.has_wildcard);
+    let route_generics = if any_lifetime { quote!(<'p>) } else { quote!() };
+
+    let mut items = vec![];
+    let mut variants = vec![];
+    let mut arms = vec![];
+    let mut tree = Node::default();
+
+    for (i, route) in routes.iter().enumerate() {
+        let Route {
+            pattern,
+            variant,
+            params_ty,
+            fields,
+            has_wildcard,
+        } = route;
+        let doc = format!("The parameters of the route `{}`.", pattern);
+
+        let has_lifetime = !fields.is_empty() || *has_wildcard;
+        let mut field_defs = vec![];
+        let mut field_values = vec![];
+        for (pos, field) in fields.iter().enumerate() {
+            let doc = format!(
+                "The value of the parameter `{}`.",
+                field.to_string().trim_start_matches("r#")
+            );
+            field_defs.push(quote! {
+                #[doc = #doc]
+                pub #field: &'p str
+            });
+            field_values.push(quote! {
+                #field: {
+                    let (start, end) = params[#pos];
+                    &path[start..end]
+                }
+            });
+        }
+        if *has_wildcard {
+            field_defs.push(quote! {
+                /// The value of the wildcard parameter.
+                pub wildcard: &'p str
+            });
+            field_values.push(quote! {
+                wildcard: {
+                    let (start, end) = wildcard?;
+                    &path[start..end]
+                }
+            });
+        }
+
+        if has_lifetime {
+            items.push(quote! {
+                #[doc = #doc]
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+                pub struct #params_ty<'p> {
+                    #(#field_defs,)*
+                }
+            });
+            variants.push(quote! {
+                #[doc = #doc]
+                #variant(#params_ty<'p>)
+            });
+            arms.push(quote! {
+                #i => Some(Route::#variant(#params_ty { #(#field_values,)* }))
+            });
+        } else {
+            items.push(quote! {
+                #[doc = #doc]
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+                pub struct #params_ty;
+            });
+            variants.push(quote! {
+                #[doc = #doc]
+                #variant(#params_ty)
+            });
+            arms.push(quote! {
+                #i => Some(Route::#variant(#params_ty))
+            });
+        }
+
+        tree.insert(&tokenize(pattern), i);
+    }
+
+    let path_lifetime = if any_lifetime {
+        quote!(&'p str)
+    } else {
+        quote!(&str)
+    };
+    let mut searches = vec![];
+    let search = tree.expand(&mut searches);
+
+    Ok(quote! {
+        #(#items)*
+
+        /// The routes defined by `routes!`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Route #route_generics {
+            #(#variants,)*
+        }
+
+        impl #route_generics Route #route_generics {
+            /// Recognizes the provided path and extracts the typed parameters.
+            pub fn recognize(path: #path_lifetime) -> Option<Self> {
+                #(#searches)*
+
+                let mut params = vec![];
+                let mut wildcard = None;
+                match #search(path.as_bytes(), 0, &mut params, &mut wildcard)? {
+                    #(#arms,)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}
+
+/// An element of a validated route pattern.
+#[derive(Debug, PartialEq)]
+enum Token<'p> {
+    Static(&'p str),
+    Param,
+    Constrained(&'p str),
+    /// A wildcard followed by its slug.
+    Wildcard(&'p str),
+}
+
+/// Splits a pattern that has been accepted by the router into its elements.
+fn tokenize(pattern: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = pattern;
+    while !rest.is_empty() {
+        if let Some(slug) = rest.strip_prefix('*') {
+            tokens.push(Token::Wildcard(slug));
+            break;
+        }
+        if rest.starts_with(':') {
+            let name_end = rest.find(['/', '<']).unwrap_or(rest.len());
+            if rest[name_end..].starts_with('<') {
+                let end = name_end + constraint_len(&rest[name_end..]);
+                tokens.push(Token::Constrained(&rest[name_end + 1..end - 1]));
+                rest = &rest[end..];
+            } else {
+                tokens.push(Token::Param);
+                rest = &rest[name_end..];
+            }
+            continue;
+        }
+        let end = rest.find([':', '*']).unwrap_or(rest.len());
+        tokens.push(Token::Static(&rest[..end]));
+        rest = &rest[end..];
+    }
+    tokens
+}
+
+/// Returns the length of the constraint at the start of `s`, including the angle brackets.
+fn constraint_len(s: &str) -> usize {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => (),
+        }
+    }
+    s.len()
+}
+
+/// A node of the routing tree, which is built at compile time and expanded
+/// into the matching code.
+///
+/// The shape of the tree and the order of the children are the same as in
+/// `Router`, so the generated code selects the same route as the router would.
+#[derive(Debug, Default)]
+struct Node {
+    route: Option<usize>,
+    static_segments: Vec<(Vec<u8>, Node)>,
+    constrained_segments: Vec<(String, Node)>,
+    param_segment: Option<Box<Node>>,
+    wildcard_segments: Vec<(String, Node)>,
+}
+
+impl Node {
+    fn insert(&mut self, tokens: &[Token<'_>], route: usize) {
+        let (token, rest) = match tokens.split_first() {
+            Some(split) => split,
+            None => {
+                self.route.get_or_insert(route);
+                return;
+            }
+        };
+        let child = match *token {
+            Token::Static(segment) => self.static_child(segment.as_bytes()),
+            Token::Param => self.param_segment.get_or_insert_with(Default::default),
+            Token::Constrained(pattern) => {
+                let pos = match self
+                    .constrained_segments
+                    .iter()
+                    .position(|(p, _)| p == pattern)
+                {
+                    Some(pos) => pos,
+                    None => {
+                        self.constrained_segments
+                            .push((pattern.to_owned(), Node::default()));
+                        self.constrained_segments.len() - 1
+                    }
+                };
+                &mut self.constrained_segments[pos].1
+            }
+            Token::Wildcard(slug) => {
+                // The most specific slug is tried first, as in `Router`.
+                let pos = match self.wildcard_segments.iter().position(|(s, _)| s == slug) {
+                    Some(pos) => pos,
+                    None => {
+                        let pos = self
+                            .wildcard_segments
+                            .iter()
+                            .position(|(s, _)| s.len() < slug.len())
+                            .unwrap_or(self.wildcard_segments.len());
+                        self.wildcard_segments
+                            .insert(pos, (slug.to_owned(), Node::default()));
+                        pos
+                    }
+                };
+                &mut self.wildcard_segments[pos].1
+            }
+        };
+        child.insert(rest, route);
+    }
+
+    /// Returns the node reached after `segment`, splitting the static segments
+    /// so that no two of them share a common prefix.
+    fn static_child(&mut self, segment: &[u8]) -> &mut Node {
+        if segment.is_empty() {
+            return self;
+        }
+        let pos = match self
+            .static_segments
+            .iter()
+            .position(|(s, _)| s[0] == segment[0])
+        {
+            Some(pos) => pos,
+            None => {
+                self.static_segments
+                    .push((segment.to_owned(), Node::default()));
+                return &mut self.static_segments.last_mut().unwrap().1;
+            }
+        };
+        let (s, child) = &mut self.static_segments[pos];
+        let lcp = s.iter().zip(segment).take_while(|(a, b)| a == b).count();
+        if lcp < s.len() {
+            let suffix = s.split_off(lcp);
+            let node = std::mem::take(child);
+            child.static_segments.push((suffix, node));
+        }
+        child.static_child(&segment[lcp..])
+    }
+
+    /// Generates the function searching for a route below this node, and
+    /// returns its name.
+    ///
+    /// The function returns the position of the route in the table, and
+    /// records the spans of the parameters and the wildcard.
+    fn expand(&self, searches: &mut Vec<TokenStream>) -> Ident {
+        let name = format_ident!("search_{}", searches.len());
+        let index = searches.len();
+        searches.push(TokenStream::new());
+
+        let mut branches = vec![];
+        for (segment, child) in &self.static_segments {
+            let child = child.expand(searches);
+            let len = segment.len();
+            let segment = Literal::byte_string(segment);
+            branches.push(quote! {
+                if path[offset..].starts_with(#segment) {
+                    if let Some(route) = #child(path, offset + #len, params, wildcard) {
+                        return Some(route);
+                    }
+                }
+            });
+        }
+        for (pattern, child) in &self.constrained_segments {
+            let child = child.expand(searches);
+            branches.push(quote! {
+                static CONSTRAINT: ::tsukuyomi_router::__private::RegexConstraint =
+                    ::tsukuyomi_router::__private::RegexConstraint::new(#pattern);
+                for end in CONSTRAINT.ends(path, offset) {
+                    params.push((offset, end));
+                    if let Some(route) = #child(path, end, params, wildcard) {
+                        return Some(route);
+                    }
+                    params.pop();
+                }
+            });
+        }
+        if let Some(child) = &self.param_segment {
+            let child = child.expand(searches);
+            branches.push(quote! {
+                let end = path[offset..]
+                    .iter()
+                    .position(|&c| c == b'/')
+                    .map_or(path.len(), |pos| offset + pos);
+                params.push((offset, end));
+                if let Some(route) = #child(path, end, params, wildcard) {
+                    return Some(route);
+                }
+                params.pop();
+            });
+        }
+        for (slug, child) in &self.wildcard_segments {
+            let child = child.expand(searches);
+            let len = slug.len();
+            let slug = Literal::byte_string(slug.as_bytes());
+            branches.push(quote! {
+                if offset + #len <= path.len() && path.ends_with(#slug) {
+                    *wildcard = Some((offset, path.len() - #len));
+                    if let Some(route) = #child(path, path.len(), params, wildcard) {
+                        return Some(route);
+                    }
+                    *wildcard = None;
+                }
+            });
+        }
+
+        let route = match self.route {
+            Some(route) => quote!(Some(#route)),
+            None => quote!(None),
+        };
+        searches[index] = quote! {
+            #[allow(unused_variables, clippy::ptr_arg)]
+            fn #name(
+                path: &[u8],
+                offset: usize,
+                params: &mut Vec<(usize, usize)>,
+                wildcard: &mut Option<(usize, usize)>,
+            ) -> Option<usize> {
+                if path.len() <= offset {
+                    return #route;
+                }
+                #({ #branches })*
+                None
+            }
+        };
+        name
+    }
+}
+
+/// Replaces the parameter names in the pattern, so that patterns matching
+/// exactly the same set of paths compare equal.
+fn normalize(pattern: &str) -> String {
+    let mut normalized = String::with_capacity(pattern.len());
+    let mut in_param = false;
+    let mut depth = 0;
+    let mut escaped = false;
+    for c in pattern.chars() {
+        // the constraint of a parameter is kept as it is.
+        if depth > 0 {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => (),
+            }
+            normalized.push(c);
+            continue;
+        }
+        if in_param && c != '/' && c != '<' {
+            continue;
+        }
+        if in_param && c == '<' {
+            depth = 1;
+        }
+        in_param = c == ':';
+        normalized.push(c);
+    }
+    normalized
+}
+
+fn field_ident(name: &str, span: Span) -> Ident {
+    let name = name.replace('-', "_");
+    match syn::parse_str::<Ident>(&name) {
+        Ok(..) => Ident::new(&name, span),
+        Err(..) => Ident::new_raw(&name, span),
+    }
+}
+
+fn to_upper_camel_case(s: &str) -> String {
+    let mut camel = String::with_capacity(s.len());
+    let mut upper = true;
+    for c in s.trim_start_matches("r#").chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_pattern() {
+        assert_eq!(normalize("/users/:id/books"), "/users/:/books");
+        assert_eq!(normalize("/:year/:month"), "/:/:");
+        assert_eq!(normalize("/static/*/index.html"), "/static/*/index.html");
+        assert_eq!(
+            normalize("/archive/:date<(?:\\d+)/\\d+>/:id"),
+            "/archive/:<(?:\\d+)/\\d+>/:"
+        );
+    }
+
+    #[test]
+    fn tokenize_pattern() {
+        assert_eq!(
+            tokenize("/users/:id/books"),
+            vec![
+                Token::Static("/users/"),
+                Token::Param,
+                Token::Static("/books")
+            ]
+        );
+        assert_eq!(
+            tokenize("/archive/:date<(?:\\d+)/\\d+>/*.html"),
+            vec![
+                Token::Static("/archive/"),
+                Token::Constrained("(?:\\d+)/\\d+"),
+                Token::Static("/"),
+                Token::Wildcard(".html"),
+            ]
+        );
+    }
+
+    #[test]
+    fn upper_camel_case() {
+        assert_eq!(to_upper_camel_case("users_show"), "UsersShow");
+        assert_eq!(to_upper_camel_case("index"), "Index");
+        assert_eq!(to_upper_camel_case("r#type"), "Type");
+    }
+
+    #[test]
+    fn validate_patterns() {
+        let entries = |tokens: TokenStream| {
+            syn::parse2::<RouteTable>(tokens)
+                .unwrap()
+                .entries
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        let routes = validate(&entries(quote! {
+            "/users/:id" => user,
+            "/static/*" => assets,
+        }))
+        .unwrap();
+        assert_eq!(routes[0].fields, vec!["id"]);
+        assert!(routes[1].has_wildcard);
+
+        let err = validate(&entries(quote!("/users/:id:name" => user)))
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("invalid route pattern"));
+
+        let err = validate(&entries(quote! {
+            "/users/:id" => user,
+            "/users/:name" => user_by_name,
+        }))
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "the route pattern conflicts with `/users/:id`"
+        );
+
+        let routes = validate(&entries(quote! {
+            "/users/:id" => user,
+            "/users/*" => user_files,
+            "/users/new" => new_user,
+        }))
+        .unwrap();
+        assert_eq!(routes.len(), 3);
+
+        let err = validate(&entries(quote! {
+            "/items/:id<\\d+>" => item,
+            "/items/:code<[0-9a-f]+>" => item_by_code,
+        }))
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "the route pattern conflicts with `/items/:id<\\d+>`, since both match \"/items/0\" \
+             and the selected route depends on the order of the entries"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+mod api {
+    use tsukuyomi_router_macros::routes;
+
+    routes! {
+        "/posts" => posts,
+        "/posts/:post" => post,
+        "/posts/:post/comments/:comment-id" => post_comment,
+        "/static/*/index.html" => static_index,
+    }
+}
+
+mod overlapping {
+    use tsukuyomi_router_macros::routes;
+
+    routes! {
+        "/users/:id" => user,
+        "/users/*" => user_files,
+        "/users/new" => new_user,
+        "/items/:id<\\d+>" => item,
+        "/items/:slug" => item_by_slug,
+        "/archive/:date<\\d{4}/\\d{2}>/:title" => archive,
+    }
+}
+
+mod no_params {
+    use tsukuyomi_router_macros::routes;
+
+    routes! {
+        "/" => index,
+        "/about" => about,
+    }
+}
+
+#[test]
+fn recognize() {
+    use api::*;
+
+    assert_eq!(Route::recognize("/posts"), Some(Route::Posts(PostsParams)));
+    assert_eq!(
+        Route::recognize("/posts/42"),
+        Some(Route::Post(PostParams { post: "42" }))
+    );
+    assert_eq!(
+        Route::recognize("/posts/42/comments/7"),
+        Some(Route::PostComment(PostCommentParams {
+            post: "42",
+            comment_id: "7",
+        }))
+    );
+    assert_eq!(
+        Route::recognize("/static/path/to/index.html"),
+        Some(Route::StaticIndex(StaticIndexParams {
+            wildcard: "path/to",
+        }))
+    );
+    assert_eq!(Route::recognize("/users"), None);
+}
+
+#[test]
+fn recognize_without_params() {
+    use no_params::*;
+
+    assert_eq!(Route::recognize("/"), Some(Route::Index(IndexParams)));
+    assert_eq!(Route::recognize("/about"), Some(Route::About(AboutParams)));
+    assert_eq!(Route::recognize("/contact"), None);
+}
+
+#[test]
+fn recognize_overlapping() {
+    use overlapping::*;
+
+    assert_eq!(
+        Route::recognize("/users/new"),
+        Some(Route::NewUser(NewUserParams))
+    );
+    assert_eq!(
+        Route::recognize("/users/42"),
+        Some(Route::User(UserParams { id: "42" }))
+    );
+    assert_eq!(
+        Route::recognize("/users/42/avatar.png"),
+        Some(Route::UserFiles(UserFilesParams {
+            wildcard: "42/avatar.png",
+        }))
+    );
+    assert_eq!(
+        Route::recognize("/items/42"),
+        Some(Route::Item(ItemParams { id: "42" }))
+    );
+    assert_eq!(
+        Route::recognize("/items/tea"),
+        Some(Route::ItemBySlug(ItemBySlugParams { slug: "tea" }))
+    );
+    assert_eq!(
+        Route::recognize("/archive/2019/05/hello"),
+        Some(Route::Archive(ArchiveParams {
+            date: "2019/05",
+            title: "hello",
+        }))
+    );
+    assert_eq!(Route::recognize("/archive/2019/5/hello"), None);
+}
+
+#[test]
+fn recognize_same_as_router() {
+    use overlapping::*;
+    use tsukuyomi_router::Router;
+
+    let mut router = Router::new();
+    for pattern in &[
+        "/users/:id",
+        "/users/*",
+        "/users/new",
+        "/items/:id<\\d+>",
+        "/items/:slug",
+        "/archive/:date<\\d{4}/\\d{2}>/:title",
+    ] {
+        router.add_route(pattern, ()).unwrap();
+    }
+
+    for path in &[
+        "/users/new",
+        "/users/newer",
+        "/users/",
+        "/users/42/",
+        "/items/0",
+        "/items/0x",
+        "/archive/2019/05/",
+        "/archive/2019/05/hello/world",
+    ] {
+        let recognize = router.recognize(path);
+        let expected = recognize.route().map(|(route, params)| {
+            let mut values = vec![];
+            if let Some(params) = params {
+                values.extend(params.iter().map(|(_, value)| value.to_owned()));
+            }
+            (route.path().to_owned(), values)
+        });
+        let actual = Route::recognize(path).map(|route| match route {
+            Route::User(p) => ("/users/:id", vec![p.id]),
+            Route::UserFiles(p) => ("/users/*", vec![p.wildcard]),
+            Route::NewUser(..) => ("/users/new", vec![]),
+            Route::Item(p) => ("/items/:id<\\d+>", vec![p.id]),
+            Route::ItemBySlug(p) => ("/items/:slug", vec![p.slug]),
+            Route::Archive(p) => (
+                "/archive/:date<\\d{4}/\\d{2}>/:title",
+                vec![p.date, p.title],
+            ),
+        });
+        assert_eq!(
+            actual.map(|(pattern, values)| (
+                pattern.to_owned(),
+                values.into_iter().map(str::to_owned).collect()
+            )),
+            expected,
+            "{}",
+            path
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Building a router from a batch of declarations.
+
+use crate::{
+    endpoint::{EndpointId, EndpointKind},
+    error::{Error, Result},
+    guard::Guard,
+    matcher::SegmentMatcher,
+    router::Router,
+    version::{self, VersionSource},
+};
+use std::{
+    collections::HashMap,
+    ops::{RangeBounds, RangeInclusive},
+    sync::Arc,
+};
+
+/// A builder collecting the declarations of a router and validating them at once.
+///
+/// Unlike the methods of `Router`, the declarations are not checked when they
+/// are made. `RouterBuilder::build` reports every invalid declaration together,
+/// each error carrying the index of its declaration (see `Error::index`).
+///
+/// The endpoints are inserted in the order of the declarations, and the IDs of
+/// the endpoints follow that order. The built router matches the same way as
+/// one built with `Router::add_route` and the related methods.
+///
+/// The routing tree is compacted the same way whatever the order of the
+/// declarations, since the segments of a node are kept sorted. Only the order
+/// of the constrained parameters at the same position, and of the routes
+/// sharing a pattern, follows the declarations, as it decides which one is
+/// tried first.
+///
+/// ```
+/// # use tsukuyomi_router::Router;
+/// let mut builder = Router::builder();
+/// let show = builder.route("/posts/:id", "show").name("post").methods(["GET"]).id();
+/// builder.route("/posts", "index");
+/// let router = builder.build().unwrap();
+///
+/// assert_eq!(router.endpoint_by_name("post").map(|e| e.id()), Some(show));
+/// assert_eq!(router.recognize("/posts").route().map(|(r, _)| *r.data()), Some("index"));
+/// ```
+#[derive(Debug)]
+pub struct RouterBuilder<T> {
+    entries: Vec<Entry<T>>,
+    matchers: Vec<(String, Arc<dyn SegmentMatcher>)>,
+    extensions: Vec<String>,
+    version_source: Option<VersionSource>,
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    kind: EndpointKind,
+    path: String,
+    scope: Option<EndpointId>,
+    versions: Option<Result<RangeInclusive<u32>>>,
+    name: Option<String>,
+    methods: Option<Vec<String>>,
+    host: Option<String>,
+    guards: Vec<Arc<dyn Guard>>,
+    data: T,
+}
+
+impl<T> Default for RouterBuilder<T> {
+    fn default() -> Self {
+        Self {
+            entries: vec![],
+            matchers: vec![],
+            extensions: vec![],
+            version_source: None,
+        }
+    }
+}
+
+impl<T> Router<T> {
+    /// Creates a builder of a router.
+    pub fn builder() -> RouterBuilder<T> {
+        RouterBuilder::default()
+    }
+}
+
+impl<T> RouterBuilder<T> {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a route.
+    ///
+    /// See `Router::add_route`.
+    pub fn route(&mut self, path: &str, data: T) -> EntryBuilder<'_, T> {
+        self.push(EndpointKind::Route, path, None, data)
+    }
+
+    /// Declares a route available in the specified range of API versions.
+    ///
+    /// See `Router::add_versioned`.
+    pub fn versioned<R>(&mut self, path: &str, versions: R, data: T) -> EntryBuilder<'_, T>
+    where
+        R: RangeBounds<u32>,
+    {
+        let versions = version::to_inclusive(versions);
+        self.push(EndpointKind::Route, path, Some(versions), data)
+    }
+
+    /// Declares a scope.
+    ///
+    /// See `Router::add_scope`.
+    pub fn scope(&mut self, path: &str, data: T) -> EntryBuilder<'_, T> {
+        self.push(EndpointKind::Scope, path, None, data)
+    }
+
+    /// Declares the fallback of the router.
+    ///
+    /// See `Router::set_fallback`.
+    pub fn fallback(&mut self, data: T) -> EntryBuilder<'_, T> {
+        self.push(EndpointKind::Fallback, "*", None, data)
+    }
+
+    /// Declares the fallback of the scope with the specified ID.
+    ///
+    /// The ID is obtained by `EntryBuilder::id` on the declaration of the
+    /// scope, which must come before this one. See `Router::set_scope_fallback`.
+    pub fn scope_fallback(&mut self, scope: EndpointId, data: T) -> EntryBuilder<'_, T> {
+        let mut entry = self.push(EndpointKind::Fallback, "", None, data);
+        entry.entry().scope = Some(scope);
+        entry
+    }
+
+    /// Registers a segment matcher under the specified name.
+    ///
+    /// See `Router::register_matcher`. The matchers are registered before
+    /// any route is inserted, regardless of the order of the declarations.
+    pub fn register_matcher<M>(&mut self, name: &str, matcher: M) -> &mut Self
+    where
+        M: SegmentMatcher,
+    {
+        self.matchers.push((name.to_owned(), Arc::new(matcher)));
+        self
+    }
+
+    /// Registers a file extension.
+    ///
+    /// See `Router::add_extension`.
+    pub fn extension(&mut self, extension: &str) -> &mut Self {
+        self.extensions.push(extension.to_owned());
+        self
+    }
+
+    /// Sets where the requested API version is read from.
+    ///
+    /// See `Router::set_version_source`.
+    pub fn version_source(&mut self, source: VersionSource) -> &mut Self {
+        self.version_source = Some(source);
+        self
+    }
+
+    fn push(
+        &mut self,
+        kind: EndpointKind,
+        path: &str,
+        versions: Option<Result<RangeInclusive<u32>>>,
+        data: T,
+    ) -> EntryBuilder<'_, T> {
+        let index = self.entries.len();
+        self.entries.push(Entry {
+            kind,
+            path: path.to_owned(),
+            scope: None,
+            versions,
+            name: None,
+            methods: None,
+            host: None,
+            guards: vec![],
+            data,
+        });
+        EntryBuilder {
+            builder: self,
+            index,
+        }
+    }
+
+    /// Validates the declarations and builds the router.
+    ///
+    /// If any declaration is invalid, all of the errors are returned, ordered
+    /// by the index of their declarations. The errors of the router-wide
+    /// settings do not carry an index and come first.
+    pub fn build(self) -> std::result::Result<Router<T>, Vec<Error>> {
+        let Self {
+            entries,
+            matchers,
+            extensions,
+            version_source,
+        } = self;
+        let mut router = Router::new();
+        let mut errors = vec![];
+
+        if let Some(source) = version_source {
+            router.set_version_source(source);
+        }
+        for (name, matcher) in matchers {
+            if let Err(err) = router.insert_matcher(&name, matcher) {
+                errors.push(err);
+            }
+        }
+        for extension in &extensions {
+            if let Err(err) = router.add_extension(extension) {
+                errors.push(err);
+            }
+        }
+
+        let mut names = HashMap::new();
+        let mut duplicates = vec![false; entries.len()];
+        for (index, entry) in entries.iter().enumerate() {
+            if let Some(ref name) = entry.name {
+                if let Some(&other) = names.get(name.as_str()) {
+                    errors.push(
+                        Error::from(format!(
+                            "the endpoint name `{}` is already used by entry #{}",
+                            name, other
+                        ))
+                        .at_index(index),
+                    );
+                    duplicates[index] = true;
+                } else {
+                    names.insert(name.as_str(), index);
+                }
+            }
+        }
+
+        for (index, entry) in entries.into_iter().enumerate() {
+            if let Err(err) = insert(&mut router, EndpointId(index), entry, duplicates[index]) {
+                errors.push(err.at_index(index));
+            }
+        }
+
+        if !errors.is_empty() {
+            errors.sort_by_key(Error::index);
+            return Err(errors);
+        }
+        Ok(router)
+    }
+}
+
+fn insert<T>(
+    router: &mut Router<T>,
+    id: EndpointId,
+    entry: Entry<T>,
+    duplicate: bool,
+) -> Result<()> {
+    let id = match (entry.kind, entry.versions) {
+        (EndpointKind::Fallback, _) => match entry.scope {
+            Some(scope) => router.insert_scope_fallback(id, scope, entry.data)?,
+            None => router.insert_fallback(id, entry.data)?,
+        },
+        (_, Some(versions)) => router.insert_versioned(id, &entry.path, versions?, entry.data)?,
+        (kind, None) => router.insert_endpoint(id, kind, &entry.path, entry.data)?,
+    };
+    match entry.name {
+        Some(ref name) if !duplicate => router.set_name(id, name)?,
+        _ => (),
+    }
+    if let Some(methods) = entry.methods {
+        router.set_methods(id, methods)?;
+    }
+    if let Some(ref host) = entry.host {
+        router.set_host(id, host)?;
+    }
+    if let Some(endpoint) = router.endpoint_mut(id) {
+        endpoint.guards.extend(entry.guards);
+    }
+    Ok(())
+}
+
+/// A handle to a declaration in `RouterBuilder`, used to set its options.
+#[derive(Debug)]
+pub struct EntryBuilder<'b, T> {
+    builder: &'b mut RouterBuilder<T>,
+    index: usize,
+}
+
+impl<'b, T> EntryBuilder<'b, T> {
+    fn entry(&mut self) -> &mut Entry<T> {
+        &mut self.builder.entries[self.index]
+    }
+
+    /// Returns the ID that the endpoint will have in the built router.
+    pub fn id(&self) -> EndpointId {
+        EndpointId(self.index)
+    }
+
+    /// Associates a name with the endpoint.
+    ///
+    /// See `Router::set_name`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.entry().name = Some(name.to_owned());
+        self
+    }
+
+    /// Sets the list of HTTP methods accepted by the endpoint.
+    ///
+    /// See `Router::set_methods`.
+    pub fn methods<I>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.entry().methods = Some(methods.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Restricts the endpoint to the provided host name.
+    ///
+    /// See `Router::set_host`.
+    pub fn host(mut self, host: &str) -> Self {
+        self.entry().host = Some(host.to_owned());
+        self
+    }
+
+    /// Attaches a guard to the endpoint.
+    ///
+    /// See `Router::add_guard`.
+    pub fn guard<G>(mut self, guard: G) -> Self
+    where
+        G: Guard,
+    {
+        self.entry().guards.push(Arc::new(guard));
+        self
+    }
+}
@@ -0,0 +1,108 @@
+//! Detecting routes that match the same paths.
+
+use crate::{
+    endpoint::{Endpoint, EndpointId},
+    router::Router,
+    tree::example_path,
+};
+use std::fmt;
+
+/// A pair of routes that match at least one common path.
+///
+/// This value is created by `Router::check_conflicts`.
+#[derive(Debug)]
+pub struct Conflict<'r, T> {
+    routes: (&'r Endpoint<T>, &'r Endpoint<T>),
+    example: String,
+    winner: &'r Endpoint<T>,
+}
+
+impl<'r, T> Conflict<'r, T> {
+    /// Returns the two conflicting routes, in the order they were added.
+    pub fn routes(&self) -> (&'r Endpoint<T>, &'r Endpoint<T>) {
+        self.routes
+    }
+
+    /// Returns a path matched by both routes.
+    pub fn example(&self) -> &str {
+        &self.example
+    }
+
+    /// Returns the route that the router currently selects for `example()`.
+    ///
+    /// This is usually one of the conflicting routes, but may be a third,
+    /// more specific route that also matches the example path. The winner is
+    /// determined from the path alone: the versions, methods, hosts and guards
+    /// of the routes are not taken into account, so a request may be routed
+    /// to another route depending on these.
+    pub fn winner(&self) -> &'r Endpoint<T> {
+        self.winner
+    }
+}
+
+impl<'r, T> fmt::Display for Conflict<'r, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` and `{}` both match {:?} (recognized as `{}`)",
+            self.routes.0.path(),
+            self.routes.1.path(),
+            self.example,
+            self.winner.path(),
+        )
+    }
+}
+
+impl<T> Router<T> {
+    /// Returns every pair of routes that can match the same path.
+    ///
+    /// The patterns are compared symbolically, assuming that parameters and
+    /// wildcards capture at least one byte. Parameters constrained by a regular
+    /// expression or a segment matcher are compared as wildcards and plain
+    /// parameters respectively, and a bounded number of common paths is tried
+    /// to find an example satisfying their constraints. A conflict may thus be
+    /// missed if the constraints only accept unusual values. Routes registered
+    /// with the same pattern are not reported, since they are told apart by
+    /// their methods, guards or versions. The pairs are ordered by the
+    /// positions of the routes in the router.
+    pub fn check_conflicts(&self) -> Vec<Conflict<'_, T>> {
+        let mut patterns = self.tree.route_patterns();
+        patterns.sort_by_key(|&(id, _)| id.0);
+
+        let mut conflicts = vec![];
+        for (i, (a, pattern_a)) in patterns.iter().enumerate() {
+            for (b, pattern_b) in &patterns[i + 1..] {
+                if pattern_a == pattern_b {
+                    continue;
+                }
+                // The patterns only approximate the constrained parameters,
+                // so make sure that both routes actually match the example.
+                let matches = |example: &[u8], id: EndpointId| {
+                    self.tree
+                        .recognize_with(example, &|route| route == id)
+                        .route
+                        == Some(id)
+                };
+                let example = match example_path(pattern_a, pattern_b, &mut |example| {
+                    matches(example, *a) && matches(example, *b)
+                }) {
+                    Some(example) => example,
+                    None => continue,
+                };
+                let winner = self
+                    .tree
+                    .recognize(&example)
+                    .route
+                    .and_then(|id| self.endpoint(id))
+                    .expect("the example path matches at least two routes");
+
+                conflicts.push(Conflict {
+                    routes: (&self[*a], &self[*b]),
+                    example: String::from_utf8_lossy(&example).into_owned(),
+                    winner,
+                });
+            }
+        }
+        conflicts
+    }
+}
@@ -1,17 +1,56 @@
-use crate::param::ParamNames;
-use std::ops::{Deref, DerefMut};
+use crate::{guard::Guard, param::ParamNames};
+use std::{
+    ops::{Deref, RangeInclusive},
+    sync::Arc,
+};
 
 /// The identifier of `Endpoint`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EndpointId(pub(crate) usize);
 
+/// The kind of `Endpoint`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EndpointKind {
+    /// An endpoint registered by `Router::add_route`.
+    Route,
+    /// An endpoint registered by `Router::add_scope`.
+    Scope,
+    /// An endpoint registered by `Router::set_fallback` or `Router::set_scope_fallback`.
+    Fallback,
+}
+
 /// An endpoint in `Router`.
+///
+/// The associated data is shared between the clones of a router.
 #[derive(Debug)]
 pub struct Endpoint<T> {
     pub(crate) id: EndpointId,
+    pub(crate) kind: EndpointKind,
     pub(crate) path: String,
+    pub(crate) name: Option<String>,
+    pub(crate) methods: Vec<String>,
+    pub(crate) host: Option<String>,
+    pub(crate) versions: Option<RangeInclusive<u32>>,
+    pub(crate) guards: Vec<Arc<dyn Guard>>,
     pub(crate) names: Option<ParamNames>,
-    pub(crate) data: T,
+    pub(crate) data: Arc<T>,
+}
+
+impl<T> Clone for Endpoint<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            kind: self.kind,
+            path: self.path.clone(),
+            name: self.name.clone(),
+            methods: self.methods.clone(),
+            host: self.host.clone(),
+            versions: self.versions.clone(),
+            guards: self.guards.clone(),
+            names: self.names.clone(),
+            data: self.data.clone(),
+        }
+    }
 }
 
 impl<T> Endpoint<T> {
@@ -20,19 +59,99 @@ impl<T> Endpoint<T> {
         self.id
     }
 
+    /// Returns the kind of this endpoint.
+    pub fn kind(&self) -> EndpointKind {
+        self.kind
+    }
+
     /// Returns the original path of this endpoint.
     pub fn path(&self) -> &str {
         &self.path
     }
 
+    /// Returns an iterator over the names of the parameters in the path of this endpoint.
+    ///
+    /// The wildcard parameter is not included. Use `has_wildcard` to check its existence.
+    pub fn param_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.names
+            .iter()
+            .flat_map(|names| (0..names.names.len()).filter_map(move |i| names.name(i)))
+    }
+
+    /// Returns whether the path of this endpoint contains a wildcard parameter.
+    pub fn has_wildcard(&self) -> bool {
+        self.names.as_ref().is_some_and(|names| names.has_wildcard)
+    }
+
+    /// Returns the name of this endpoint, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the list of HTTP methods associated with this endpoint.
+    ///
+    /// An empty list means that the endpoint accepts any method.
+    pub fn methods(&self) -> &[String] {
+        &self.methods
+    }
+
+    /// Returns the host name that this endpoint is restricted to, if any.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Returns the range of API versions of this endpoint, if it is versioned.
+    ///
+    /// See `Router::add_versioned`.
+    pub fn versions(&self) -> Option<&RangeInclusive<u32>> {
+        self.versions.as_ref()
+    }
+
+    /// Returns whether this endpoint accepts the specified HTTP method.
+    pub(crate) fn allows_method(&self, method: &str) -> bool {
+        self.methods.is_empty() || self.methods.iter().any(|m| m == method)
+    }
+
+    /// Returns whether this endpoint accepts the specified host name.
+    ///
+    /// The port number in `host` is ignored, and IPv6 addresses are compared
+    /// without their brackets.
+    pub(crate) fn matches_host(&self, host: Option<&str>) -> bool {
+        match (&self.host, host) {
+            (None, _) => true,
+            (Some(expected), Some(host)) => {
+                strip_brackets(expected).eq_ignore_ascii_case(strip_brackets(strip_port(host)))
+            }
+            (Some(..), None) => false,
+        }
+    }
+
     /// Returns a reference to the data associated with this endpoint.
     pub fn data(&self) -> &T {
         &self.data
     }
 
-    /// Returns a reference to the data associated with this endpoint.
-    pub fn data_mut(&mut self) -> &mut T {
-        &mut self.data
+    /// Returns a shared handle to the data associated with this endpoint.
+    pub fn shared_data(&self) -> Arc<T> {
+        self.data.clone()
+    }
+
+    /// Returns a mutable reference to the data associated with this endpoint,
+    /// or `None` if the data is shared with a clone of the router or with a
+    /// handle returned by `Endpoint::shared_data`.
+    pub fn try_data_mut(&mut self) -> Option<&mut T> {
+        Arc::get_mut(&mut self.data)
+    }
+
+    /// Returns a mutable reference to the data associated with this endpoint.
+    ///
+    /// If the data is shared, it is cloned first so that the other router
+    /// or handle is not affected.
+    pub fn make_data_mut(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        Arc::make_mut(&mut self.data)
     }
 }
 
@@ -44,8 +163,20 @@ impl<T> Deref for Endpoint<T> {
     }
 }
 
-impl<T> DerefMut for Endpoint<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.data_mut()
+/// Removes the port number from a host, e.g. `example.com:8080` or `[::1]:8080`.
+///
+/// An IPv6 address is only separated from the port if it is enclosed in brackets.
+fn strip_port(host: &str) -> &str {
+    match host.rfind(']') {
+        Some(end) => &host[..=end],
+        None if host.matches(':').count() == 1 => host.split(':').next().unwrap_or(host),
+        None => host,
     }
 }
+
+/// Removes the brackets around an IPv6 address, e.g. `[::1]`.
+fn strip_brackets(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
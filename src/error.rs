@@ -4,6 +4,7 @@ use std::{
     borrow::Cow, //
     error,
     fmt,
+    io,
 };
 
 pub type Result<T = ()> = std::result::Result<T, Error>;
@@ -14,6 +15,42 @@ pub struct Error(ErrorKind);
 #[derive(Debug)]
 enum ErrorKind {
     Msg(Cow<'static, str>),
+    Io(io::Error),
+    Param(ParamError),
+    Line { line: usize, cause: Box<Error> },
+    Index { index: usize, cause: Box<Error> },
+}
+
+impl Error {
+    pub(crate) fn at_line(self, line: usize) -> Self {
+        Error(ErrorKind::Line {
+            line,
+            cause: Box::new(self),
+        })
+    }
+
+    /// Returns the line number in the route table where this error occurred, if any.
+    pub fn line(&self) -> Option<usize> {
+        match self.0 {
+            ErrorKind::Line { line, .. } => Some(line),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn at_index(self, index: usize) -> Self {
+        Error(ErrorKind::Index {
+            index,
+            cause: Box::new(self),
+        })
+    }
+
+    /// Returns the index of the declaration in `RouterBuilder` where this error occurred, if any.
+    pub fn index(&self) -> Option<usize> {
+        match self.0 {
+            ErrorKind::Index { index, .. } => Some(index),
+            _ => None,
+        }
+    }
 }
 
 impl From<&'static str> for Error {
@@ -28,17 +65,139 @@ impl From<String> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error(ErrorKind::Io(err))
+    }
+}
+
+impl From<ParamError> for Error {
+    fn from(err: ParamError) -> Self {
+        Error(ErrorKind::Param(err))
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.0 {
-            ErrorKind::Msg(ref msg) => f.write_str(&*msg),
+            ErrorKind::Msg(ref msg) => f.write_str(msg),
+            ErrorKind::Io(ref err) => fmt::Display::fmt(err, f),
+            ErrorKind::Param(ref err) => fmt::Display::fmt(err, f),
+            ErrorKind::Line { line, ref cause } => write!(f, "line {}: {}", line, cause),
+            ErrorKind::Index { index, ref cause } => write!(f, "entry #{}: {}", index, cause),
         }
     }
 }
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        None
+        match self.0 {
+            ErrorKind::Msg(..) => None,
+            ErrorKind::Io(ref err) => Some(err),
+            ErrorKind::Param(ref err) => Some(err),
+            ErrorKind::Line { ref cause, .. } => Some(&**cause),
+            ErrorKind::Index { ref cause, .. } => Some(&**cause),
+        }
+    }
+}
+
+/// An error that occurs when extracting a parameter value from `Params`.
+#[derive(Debug)]
+pub struct ParamError {
+    name: Option<String>,
+    position: Option<usize>,
+    kind: ParamErrorKind,
+}
+
+#[derive(Debug)]
+enum ParamErrorKind {
+    Missing,
+    Parse {
+        value: String,
+        cause: Box<dyn error::Error + Send + Sync>,
+    },
+}
+
+impl ParamError {
+    pub(crate) fn missing(name: Option<&str>, position: Option<usize>) -> Self {
+        Self {
+            name: name.map(ToOwned::to_owned),
+            position,
+            kind: ParamErrorKind::Missing,
+        }
+    }
+
+    pub(crate) fn parse(
+        name: Option<&str>,
+        position: Option<usize>,
+        value: &str,
+        cause: impl Into<Box<dyn error::Error + Send + Sync>>,
+    ) -> Self {
+        Self {
+            name: name.map(ToOwned::to_owned),
+            position,
+            kind: ParamErrorKind::Parse {
+                value: value.to_owned(),
+                cause: cause.into(),
+            },
+        }
+    }
+
+    /// Returns the name of the parameter, if known.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the position of the parameter, if known.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Returns `true` if the parameter was not captured.
+    pub fn is_missing(&self) -> bool {
+        match self.kind {
+            ParamErrorKind::Missing => true,
+            ParamErrorKind::Parse { .. } => false,
+        }
+    }
+
+    /// Returns `true` if the parameter was captured but its value failed to parse.
+    pub fn is_parse_error(&self) -> bool {
+        !self.is_missing()
+    }
+
+    /// Returns the raw value of the parameter if it failed to parse.
+    pub fn value(&self) -> Option<&str> {
+        match self.kind {
+            ParamErrorKind::Missing => None,
+            ParamErrorKind::Parse { ref value, .. } => Some(value),
+        }
+    }
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, self.position) {
+            (Some(name), _) => write!(f, "the parameter `{}`", name)?,
+            (None, Some(position)) => write!(f, "the parameter at position {}", position)?,
+            (None, None) => f.write_str("the parameter")?,
+        }
+        match self.kind {
+            ParamErrorKind::Missing => f.write_str(" is missing"),
+            ParamErrorKind::Parse {
+                ref value,
+                ref cause,
+            } => write!(f, " has an invalid value {:?}: {}", value, cause),
+        }
+    }
+}
+
+impl error::Error for ParamError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.kind {
+            ParamErrorKind::Missing => None,
+            ParamErrorKind::Parse { ref cause, .. } => Some(&**cause),
+        }
     }
 }
 
@@ -0,0 +1,292 @@
+//! Tracing the recognition of a path.
+
+use crate::{
+    endpoint::EndpointId, //
+    router::Router,
+};
+use std::fmt;
+
+/// A step taken by the router while recognizing a path.
+///
+/// The offsets and spans are byte positions in the path of the preceding
+/// `Step::Start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// The recognition of a candidate path started.
+    ///
+    /// The candidate differs from the requested path if the version prefix
+    /// or a file extension was stripped off.
+    Start {
+        /// The candidate path.
+        path: String,
+        /// The requested API version, if any.
+        version: Option<u32>,
+    },
+    /// A node in the routing tree was visited.
+    Visit {
+        /// The position in the path when the node was reached.
+        offset: usize,
+        /// The routes terminated at this node.
+        routes: Vec<EndpointId>,
+        /// The scope terminated at this node, if any.
+        scope: Option<EndpointId>,
+    },
+    /// A scope was entered.
+    EnterScope(EndpointId),
+    /// A static segment was compared with the path.
+    Static {
+        /// The segment in the routing tree.
+        segment: String,
+        /// The position in the path where the comparison started.
+        offset: usize,
+        /// Whether the segment matched.
+        matched: bool,
+    },
+    /// A parameter was captured.
+    Param {
+        /// The start position of the captured value.
+        start: usize,
+        /// The end position of the captured value.
+        end: usize,
+    },
+    /// A parameter constrained by a regular expression was compared with the path.
+    ///
+    /// A constrained parameter may be compared several times, once for each
+    /// segment boundary at which its value could end.
+    Constraint {
+        /// The regular expression of the constraint.
+        pattern: String,
+        /// The start position of the (would-be) captured value.
+        start: usize,
+        /// The end position of the captured value, or `start` if rejected.
+        end: usize,
+        /// Whether the constraint matched.
+        matched: bool,
+    },
+    /// A wildcard segment was compared with the remaining path.
+    Wildcard {
+        /// The slug following the wildcard.
+        slug: String,
+        /// The start position of the (would-be) captured value.
+        start: usize,
+        /// The end position of the (would-be) captured value.
+        end: usize,
+        /// Whether the slug matched the end of the path.
+        matched: bool,
+    },
+    /// A route terminating at the visited node was rejected.
+    Rejected {
+        /// The rejected route.
+        route: EndpointId,
+        /// The reason of the rejection.
+        reason: Rejection,
+    },
+    /// The preceding branch did not lead to a route, and the recognition
+    /// resumed at an earlier position to try the next candidate.
+    Backtrack {
+        /// The position in the path where the recognition resumed.
+        offset: usize,
+    },
+}
+
+/// The reason why a route whose path matched was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The route is not available in the requested API version.
+    Version,
+    /// The route is restricted to another host.
+    Host,
+    /// A guard of the route rejected the input.
+    Guard,
+    /// The route does not accept the method of the request.
+    Method,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rejection::Version => "version not available",
+            Rejection::Host => "host mismatch",
+            Rejection::Guard => "guard rejected",
+            Rejection::Method => "method not allowed",
+        })
+    }
+}
+
+/// The final result of a traced recognition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The path matched the route.
+    Matched(EndpointId),
+    /// The whole path was consumed, but no route terminating at the reached
+    /// node accepted the input.
+    NoRoute,
+    /// No segment in the routing tree matches the remaining path.
+    Unmatched {
+        /// The position in the candidate path where the recognition stopped.
+        offset: usize,
+    },
+}
+
+/// A step-by-step trace of the recognition of a path.
+///
+/// This value is created by `Router::explain` and `Router::explain_input`.
+#[derive(Debug)]
+pub struct Explain<'r, T> {
+    pub(crate) router: &'r Router<T>,
+    pub(crate) path: &'r str,
+    /// The candidate path that produced the outcome.
+    pub(crate) candidate: &'r str,
+    pub(crate) steps: Vec<Step>,
+    pub(crate) outcome: Outcome,
+}
+
+impl<'r, T> Explain<'r, T> {
+    /// Returns the path being recognized.
+    pub fn path(&self) -> &str {
+        self.path
+    }
+
+    /// Returns the steps taken during the recognition, in order.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns the final result of the recognition.
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    fn fmt_endpoint(&self, f: &mut fmt::Formatter<'_>, id: EndpointId) -> fmt::Result {
+        write!(f, "#{}", id.0)?;
+        if let Some(endpoint) = self.router.endpoint(id) {
+            write!(f, " {}", endpoint.path())?;
+        }
+        Ok(())
+    }
+}
+
+fn span(path: &str, start: usize, end: usize) -> &str {
+    path.get(start..end).unwrap_or("")
+}
+
+impl<'r, T> fmt::Display for Explain<'r, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "recognize {:?}", self.path)?;
+        let mut path = self.path;
+        for step in &self.steps {
+            match *step {
+                Step::Start {
+                    path: ref candidate,
+                    version,
+                } => {
+                    path = candidate;
+                    write!(f, "  try {:?}", candidate)?;
+                    if let Some(version) = version {
+                        write!(f, " (version {})", version)?;
+                    }
+                    writeln!(f)?;
+                }
+                Step::Visit {
+                    offset,
+                    ref routes,
+                    scope,
+                } => {
+                    write!(f, "  visit node at {}", offset)?;
+                    for &route in routes {
+                        f.write_str(" [route ")?;
+                        self.fmt_endpoint(f, route)?;
+                        f.write_str("]")?;
+                    }
+                    if let Some(scope) = scope {
+                        f.write_str(" [scope ")?;
+                        self.fmt_endpoint(f, scope)?;
+                        f.write_str("]")?;
+                    }
+                    writeln!(f)?;
+                }
+                Step::EnterScope(scope) => {
+                    f.write_str("    enter scope ")?;
+                    self.fmt_endpoint(f, scope)?;
+                    writeln!(f)?;
+                }
+                Step::Static {
+                    ref segment,
+                    offset,
+                    matched,
+                } => writeln!(
+                    f,
+                    "    static {:?} at {}: {}",
+                    segment,
+                    offset,
+                    if matched { "matched" } else { "rejected" }
+                )?,
+                Step::Param { start, end } => writeln!(
+                    f,
+                    "    param {:?} ({}..{})",
+                    span(path, start, end),
+                    start,
+                    end
+                )?,
+                Step::Constraint {
+                    ref pattern,
+                    start,
+                    end,
+                    matched,
+                } => {
+                    if matched {
+                        writeln!(
+                            f,
+                            "    constraint <{}>: matched {:?} ({}..{})",
+                            pattern,
+                            span(path, start, end),
+                            start,
+                            end
+                        )?
+                    } else {
+                        writeln!(f, "    constraint <{}>: rejected", pattern)?
+                    }
+                }
+                Step::Wildcard {
+                    ref slug,
+                    start,
+                    end,
+                    matched,
+                } => {
+                    if matched {
+                        writeln!(
+                            f,
+                            "    wildcard {:?}: matched {:?} ({}..{})",
+                            format!("*{}", slug),
+                            span(path, start, end),
+                            start,
+                            end
+                        )?
+                    } else {
+                        writeln!(f, "    wildcard {:?}: rejected", format!("*{}", slug))?
+                    }
+                }
+                Step::Rejected { route, reason } => {
+                    f.write_str("    reject route ")?;
+                    self.fmt_endpoint(f, route)?;
+                    writeln!(f, ": {}", reason)?;
+                }
+                Step::Backtrack { offset } => writeln!(f, "  backtrack to {}", offset)?,
+            }
+        }
+        match self.outcome {
+            Outcome::Matched(route) => {
+                f.write_str("matched route ")?;
+                self.fmt_endpoint(f, route)?;
+                writeln!(f)
+            }
+            Outcome::NoRoute => writeln!(f, "no route is accepted at the reached node"),
+            Outcome::Unmatched { offset } => writeln!(
+                f,
+                "no segment matches the remaining path {:?} at {}",
+                span(self.candidate, offset, self.candidate.len()),
+                offset
+            ),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Guards restricting the requests accepted by a route.
+//!
+//! Several routes may be registered with the same path. During recognition,
+//! the routes of a node are tried in order, and a route is skipped if one of
+//! its guards rejects the request. If every route is skipped, the recognition
+//! falls through to the other candidates as if the path did not match.
+//!
+//! ```
+//! # use tsukuyomi_router::{guard, Input, Router};
+//! let mut router = Router::new();
+//! let json = router.add_route("/posts", "create_from_json")?;
+//! router.add_guard(json, guard::header("content-type", "application/json"))?;
+//! let form = router.add_route("/posts", "create_from_form")?;
+//! router.add_guard(form, guard::query("form"))?;
+//!
+//! let input = Input::new("/posts").with_header("Content-Type", "application/json");
+//! let recognize = router.recognize_input(&input);
+//! assert_eq!(recognize.route().map(|(r, _)| *r.data()), Some("create_from_json"));
+//!
+//! let input = Input::new("/posts?form=1");
+//! let recognize = router.recognize_input(&input);
+//! assert_eq!(recognize.route().map(|(r, _)| *r.data()), Some("create_from_form"));
+//!
+//! assert!(router.recognize("/posts").route().is_none());
+//! # Ok::<(), tsukuyomi_router::Error>(())
+//! ```
+
+use crate::query::{self, Query};
+use std::fmt;
+
+/// A predicate evaluated against the request before a route is selected.
+///
+/// This trait is implemented for closures of the form `Fn(&Input<'_>) -> bool`.
+pub trait Guard: Send + Sync + 'static {
+    /// Returns whether the route may handle the request.
+    fn check(&self, input: &Input<'_>) -> bool;
+}
+
+impl<F> Guard for F
+where
+    F: Fn(&Input<'_>) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, input: &Input<'_>) -> bool {
+        (*self)(input)
+    }
+}
+
+impl fmt::Debug for dyn Guard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Guard")
+    }
+}
+
+/// Creates a guard that requires the header `name` to be equal to `value`.
+///
+/// The values are compared case-insensitively, and any parameter following
+/// a `;` in the header value is ignored, e.g. `application/json; charset=utf-8`
+/// satisfies `header("content-type", "application/json")`.
+pub fn header(name: &str, value: &str) -> impl Guard {
+    let name = name.to_owned();
+    let value = value.to_owned();
+    move |input: &Input<'_>| {
+        input.header(&name).is_some_and(|v| {
+            let v = v.split(';').next().unwrap_or(v);
+            v.trim().eq_ignore_ascii_case(&value)
+        })
+    }
+}
+
+/// Creates a guard that requires the query string to contain the key `key`.
+pub fn query(key: &str) -> impl Guard {
+    let key = key.to_owned();
+    move |input: &Input<'_>| input.query_pairs().contains_key(&key)
+}
+
+/// The request information that guards are evaluated against.
+///
+/// An `Input` without a method represents a bare path, as passed to
+/// `Router::recognize`. Setting a method makes the router check the methods
+/// and host of each route (see `Router::set_methods` and `Router::set_host`).
+#[derive(Debug, Clone)]
+pub struct Input<'a> {
+    path: &'a str,
+    method: Option<&'a str>,
+    host: Option<&'a str>,
+    query: Option<&'a str>,
+    headers: Vec<(&'a str, &'a str)>,
+    #[cfg(feature = "http")]
+    header_map: Option<&'a ::http::HeaderMap>,
+}
+
+impl<'a> Input<'a> {
+    /// Creates an input from the provided request target.
+    ///
+    /// The query string and the fragment, if any, are split off the path.
+    pub fn new(target: &'a str) -> Self {
+        let (path, query) = query::split_path(target);
+        Self {
+            path,
+            method: None,
+            host: None,
+            query,
+            headers: vec![],
+            #[cfg(feature = "http")]
+            header_map: None,
+        }
+    }
+
+    /// Sets the request method.
+    pub fn with_method(mut self, method: &'a str) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Sets the host name of the request.
+    pub fn with_host(mut self, host: &'a str) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    /// Sets the query string, without the leading `?`.
+    ///
+    /// This replaces the query string split off the target passed to `Input::new`.
+    pub fn with_query(mut self, query: &'a str) -> Self {
+        self.query = Some(query);
+        self
+    }
+
+    /// Appends a request header.
+    pub fn with_header(mut self, name: &'a str, value: &'a str) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    #[cfg(feature = "http")]
+    pub(crate) fn with_header_map(mut self, headers: &'a ::http::HeaderMap) -> Self {
+        self.header_map = Some(headers);
+        self
+    }
+
+    /// Returns the path of the request.
+    pub fn path(&self) -> &'a str {
+        self.path
+    }
+
+    /// Returns the request method, if any.
+    pub fn method(&self) -> Option<&'a str> {
+        self.method
+    }
+
+    /// Returns the host name of the request, if any.
+    pub fn host(&self) -> Option<&'a str> {
+        self.host
+    }
+
+    /// Returns the query string, if any.
+    pub fn query(&self) -> Option<&'a str> {
+        self.query
+    }
+
+    /// Returns the value of the first header with the specified name.
+    ///
+    /// Header names are compared case-insensitively. Values that are not
+    /// valid UTF-8 are ignored.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        let value = self
+            .headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, v)| v);
+        #[cfg(feature = "http")]
+        let value = value.or_else(|| self.header_map?.get(name)?.to_str().ok());
+        value
+    }
+
+    /// Returns an iterator over the pairs in the query string.
+    pub fn query_pairs(&self) -> Query<'a> {
+        Query::new(self.query.unwrap_or(""))
+    }
+
+    /// Returns the raw value of the first query parameter with the specified key.
+    ///
+    /// A key without `=` yields an empty value.
+    pub fn query_param(&self, key: &str) -> Option<&'a str> {
+        self.query_pairs().get(key)
+    }
+}
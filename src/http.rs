@@ -0,0 +1,57 @@
+//! Integration with the `http` crate.
+
+use crate::{
+    endpoint::EndpointId, //
+    guard::Input,
+    param::Params,
+    router::{Recognize, Router},
+};
+use ::http::{header::HOST, Request};
+
+impl<'a, B> From<&'a Request<B>> for Input<'a> {
+    fn from(request: &'a Request<B>) -> Self {
+        let uri = request.uri();
+        let mut input = Input::new(uri.path())
+            .with_method(request.method().as_str())
+            .with_header_map(request.headers());
+        if let Some(host) = uri
+            .host()
+            .or_else(|| request.headers().get(HOST)?.to_str().ok())
+        {
+            input = input.with_host(host);
+        }
+        if let Some(query) = uri.query() {
+            input = input.with_query(query);
+        }
+        input
+    }
+}
+
+impl<T> Router<T> {
+    /// Searches for the route(s) matching the provided HTTP request.
+    ///
+    /// This is equivalent to calling `Router::recognize_input` with the method,
+    /// host, query and headers of the request.
+    pub fn recognize_request<'r, B>(&'r self, request: &'r Request<B>) -> Recognize<'r, T> {
+        self.recognize_input(&Input::from(request))
+    }
+
+    /// Recognizes the provided HTTP request and stores the result into its extensions.
+    ///
+    /// If a route matches, its `EndpointId` and, if the route has any parameters,
+    /// the extracted `Params<'static>` are inserted into the request extensions.
+    pub fn route_request<B>(&self, request: &mut Request<B>) -> Option<EndpointId> {
+        let (id, params) = {
+            let recognize = self.recognize_request(request);
+            let (route, params) = recognize.route()?;
+            (route.id(), params.map(Params::into_owned))
+        };
+
+        request.extensions_mut().insert(id);
+        if let Some(params) = params {
+            request.extensions_mut().insert(params);
+        }
+
+        Some(id)
+    }
+}
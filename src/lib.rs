@@ -53,6 +53,65 @@
 //! # Ok::<(), tsukuyomi_router::Error>(())
 //! ```
 //!
+//! # Priority
+//!
+//! When several routes match a path, the route is selected by the following
+//! rules. At each position in the path, the candidates are tried in this order:
+//!
+//! 1. static segments,
+//! 2. constrained parameters, in the order they were added,
+//! 3. parameters,
+//! 4. wildcards, those with the longest suffix first.
+//!
+//! If a candidate does not lead to a route, the next one is tried.
+//!
+//! The result therefore does not depend on the order in which the routes were
+//! added, except when two constrained parameters at the same position both
+//! accept the value, or when several routes share the same pattern (these are
+//! told apart by their methods, guards or versions, and tried in the order
+//! they were added).
+//!
+//! ```
+//! # use tsukuyomi_router::Router;
+//! let mut router = Router::new();
+//! router.add_route("/static/*", "catch_all")?;
+//! router.add_route("/static/*.html", "html")?;
+//! router.add_route("/static/*/index.html", "index")?;
+//! router.add_route("/static/:file", "file")?;
+//! router.add_route("/static/robots.txt", "robots")?;
+//!
+//! let data = |path| router.recognize(path).route().map(|(r, _)| *r.data());
+//! assert_eq!(data("/static/robots.txt"), Some("robots"));
+//! assert_eq!(data("/static/about.html"), Some("file"));
+//! assert_eq!(data("/static/docs/index.html"), Some("index"));
+//! assert_eq!(data("/static/docs/about.html"), Some("html"));
+//! assert_eq!(data("/static/docs/style.css"), Some("catch_all"));
+//! # Ok::<(), tsukuyomi_router::Error>(())
+//! ```
+//!
+//! # Constrained parameters
+//!
+//! A parameter may be followed by a regular expression enclosed in `<...>`,
+//! which the whole value must match. Unlike a plain parameter, the value may
+//! span several segments, and it ends at a `/` or at the end of the path.
+//! The constraint may also be the name of a matcher registered by
+//! `Router::register_matcher`.
+//!
+//! ```
+//! # use tsukuyomi_router::Router;
+//! let mut router = Router::new();
+//! router.add_route(r"/archive/:date<\d{4}/\d{2}/\d{2}>", "archive")?;
+//! router.add_route(r"/archive/:date<\d{4}/\d{2}/\d{2}>/:slug", "post")?;
+//!
+//! let recognize = router.recognize("/archive/2019/05/01/hello");
+//! let (route, params) = recognize.route().unwrap();
+//! assert_eq!(*route.data(), "post");
+//! assert_eq!(params.unwrap().get(0), Some("2019/05/01"));
+//!
+//! assert!(router.recognize("/archive/2019/5/1").route().is_none());
+//! # Ok::<(), tsukuyomi_router::Error>(())
+//! ```
+//!
 
 #![warn(
     missing_debug_implementations,
@@ -66,14 +125,45 @@
 
 #[macro_use]
 mod error;
+mod builder;
+pub mod conflict;
 mod endpoint;
+pub mod explain;
+pub mod guard;
+#[cfg(feature = "http")]
+mod http;
+mod matcher;
+mod owned;
 mod param;
+mod query;
 mod router;
+#[cfg(feature = "tower")]
+pub mod service;
+mod suggest;
+pub mod table;
 mod tree;
+pub mod typed;
+mod version;
+
+#[doc(hidden)]
+pub mod __private {
+    //! Items used by the code generated by `tsukuyomi-router-macros`.
+    //! They are not part of the public API.
+
+    pub use crate::tree::RegexConstraint;
+}
 
 pub use crate::{
-    endpoint::{Endpoint, EndpointId},
-    error::{Error, Result},
+    builder::{EntryBuilder, RouterBuilder},
+    conflict::Conflict,
+    endpoint::{Endpoint, EndpointId, EndpointKind},
+    error::{Error, ParamError, Result},
+    explain::Explain,
+    guard::{Guard, Input},
+    matcher::SegmentMatcher,
+    owned::OwnedMatch,
     param::Params,
-    router::{Recognize, Router},
+    query::Query,
+    router::{PrintTree, Recognize, Router},
+    version::VersionSource,
 };
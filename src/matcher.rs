@@ -0,0 +1,87 @@
+//! Custom matchers for parameter segments.
+
+use crate::{error::Result, router::Router};
+use std::{fmt, sync::Arc};
+
+/// A predicate deciding whether a segment is a valid value of a parameter.
+///
+/// A matcher is registered by `Router::register_matcher` and referred to by
+/// its name as the constraint of a parameter, e.g. `:code<sku>`. Unlike a
+/// regular expression, a matcher only ever sees a single segment.
+///
+/// This trait is implemented for closures of the form `Fn(&str) -> bool`.
+pub trait SegmentMatcher: Send + Sync + 'static {
+    /// Returns whether the segment is accepted as the value of the parameter.
+    fn matches(&self, seg: &str) -> bool;
+}
+
+impl<F> SegmentMatcher for F
+where
+    F: Fn(&str) -> bool + Send + Sync + 'static,
+{
+    fn matches(&self, seg: &str) -> bool {
+        (*self)(seg)
+    }
+}
+
+impl fmt::Debug for dyn SegmentMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SegmentMatcher")
+    }
+}
+
+impl<T> Router<T> {
+    /// Registers a segment matcher under the specified name.
+    ///
+    /// The constraint `<name>` of a parameter refers to the matcher if one is
+    /// registered with that name, and is otherwise compiled as a regular
+    /// expression. Hence the matcher must be registered before the routes
+    /// using it are added, and an error is returned if a route already uses
+    /// `<name>` as a regular expression.
+    ///
+    /// ```
+    /// # use tsukuyomi_router::Router;
+    /// let mut router = Router::new();
+    /// router.register_matcher("sku", |seg: &str| {
+    ///     seg.len() == 8 && seg.bytes().all(|b| b.is_ascii_alphanumeric())
+    /// })?;
+    /// router.add_route("/products/:code<sku>", "product")?;
+    /// router.add_route("/products/:name", "search")?;
+    ///
+    /// let data = |path| router.recognize(path).route().map(|(r, _)| *r.data());
+    /// assert_eq!(data("/products/AB12CD34"), Some("product"));
+    /// assert_eq!(data("/products/shoes"), Some("search"));
+    /// # Ok::<(), tsukuyomi_router::Error>(())
+    /// ```
+    pub fn register_matcher<M>(&mut self, name: &str, matcher: M) -> Result<()>
+    where
+        M: SegmentMatcher,
+    {
+        self.insert_matcher(name, Arc::new(matcher))
+    }
+
+    pub(crate) fn insert_matcher(
+        &mut self,
+        name: &str,
+        matcher: Arc<dyn SegmentMatcher>,
+    ) -> Result<()> {
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
+        {
+            bail!("only alphabet, numbers, underscore or hyphen can be used for matcher names");
+        }
+        if self.tree.matchers.contains_key(name) {
+            bail!(format!("the matcher `{}` is already registered", name));
+        }
+        if self.tree.has_regex_constraint(name) {
+            bail!(format!(
+                "the constraint `<{}>` is already used as a regular expression",
+                name
+            ));
+        }
+        self.tree.matchers.insert(name.to_owned(), matcher);
+        Ok(())
+    }
+}
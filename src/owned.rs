@@ -0,0 +1,145 @@
+//! Recognition results that do not borrow the router or the path.
+
+use crate::{
+    endpoint::{Endpoint, EndpointId},
+    param::Params,
+    query::Query,
+    router::{Recognize, Router},
+};
+use std::sync::Arc;
+
+/// An owned version of `Recognize`, which can be moved across threads and `.await` points.
+///
+/// This value is created by `Recognize::into_owned` or `Recognize::into_owned_with`.
+/// The endpoints are referred to by their IDs. If the match holds a handle
+/// to the router, the endpoints themselves are also accessible.
+#[derive(Debug)]
+pub struct OwnedMatch<T> {
+    route: Option<EndpointId>,
+    scopes: Vec<EndpointId>,
+    params: Option<Params<'static>>,
+    version: Option<u32>,
+    extension: Option<String>,
+    query: Option<String>,
+    method_not_allowed: bool,
+    router: Option<Arc<Router<T>>>,
+}
+
+impl<T> Clone for OwnedMatch<T> {
+    fn clone(&self) -> Self {
+        Self {
+            route: self.route,
+            scopes: self.scopes.clone(),
+            params: self.params.clone(),
+            version: self.version,
+            extension: self.extension.clone(),
+            query: self.query.clone(),
+            method_not_allowed: self.method_not_allowed,
+            router: self.router.clone(),
+        }
+    }
+}
+
+impl<T> OwnedMatch<T> {
+    /// Returns the ID of the matched route, if any.
+    pub fn route_id(&self) -> Option<EndpointId> {
+        self.route
+    }
+
+    /// Returns the IDs of the matched scopes, from the outermost to the innermost.
+    pub fn scope_ids(&self) -> &[EndpointId] {
+        &self.scopes
+    }
+
+    /// Returns the parameters extracted for the matched route, if it has any.
+    pub fn params(&self) -> Option<&Params<'static>> {
+        self.params.as_ref()
+    }
+
+    /// Takes the parameters extracted for the matched route out of this value.
+    pub fn take_params(&mut self) -> Option<Params<'static>> {
+        self.params.take()
+    }
+
+    /// Returns the API version that the recognition was resolved against.
+    ///
+    /// See `Recognize::version`.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// Returns the file extension that was stripped from the path, if any.
+    pub fn extension(&self) -> Option<&str> {
+        self.extension.as_deref()
+    }
+
+    /// Returns an iterator over the pairs in the query string of the recognized target.
+    pub fn query(&self) -> Query<'_> {
+        Query::new(self.query.as_deref().unwrap_or(""))
+    }
+
+    /// Returns whether the path matched a route that does not accept the requested method.
+    pub fn is_method_not_allowed(&self) -> bool {
+        self.method_not_allowed
+    }
+
+    /// Returns the router that produced this match, if a handle to it is held.
+    pub fn router(&self) -> Option<&Arc<Router<T>>> {
+        self.router.as_ref()
+    }
+
+    /// Returns a reference to the matched route.
+    ///
+    /// This returns `None` if no route matched or no handle to the router is held.
+    pub fn route(&self) -> Option<&Endpoint<T>> {
+        self.router.as_ref()?.endpoint(self.route?)
+    }
+
+    /// Returns an iterator over the matched scopes, from the outermost to the innermost.
+    ///
+    /// The iterator is empty if no handle to the router is held.
+    pub fn scopes(&self) -> impl Iterator<Item = &Endpoint<T>> + '_ {
+        self.router.iter().flat_map(move |router| {
+            self.scopes
+                .iter()
+                .filter_map(move |&id| router.endpoint(id))
+        })
+    }
+}
+
+impl<'r, T> Recognize<'r, T> {
+    /// Converts this value into an `OwnedMatch` that does not hold a handle to the router.
+    ///
+    /// The values of the parameters are copied.
+    pub fn into_owned(self) -> OwnedMatch<T> {
+        let params = self
+            .route()
+            .and_then(|(_, params)| params.map(Params::into_owned));
+        OwnedMatch {
+            route: self.route.map(|route| route.id()),
+            scopes: self.scopes.iter().map(|scope| scope.id()).collect(),
+            params,
+            version: self.version,
+            extension: self.extension.map(ToOwned::to_owned),
+            query: self.query.map(ToOwned::to_owned),
+            method_not_allowed: self.is_method_not_allowed(),
+            router: None,
+        }
+    }
+
+    /// Converts this value into an `OwnedMatch` that holds the specified handle to the router.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `router` is not the router that produced this value.
+    pub fn into_owned_with(self, router: &Arc<Router<T>>) -> OwnedMatch<T> {
+        assert!(
+            std::ptr::eq(&**router, self.router),
+            "the handle does not refer to the router that produced the match"
+        );
+        OwnedMatch {
+            router: Some(router.clone()),
+            ..self.into_owned()
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use std::borrow::Cow;
+use crate::error::ParamError;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ops::Range,
+    str::{self, FromStr},
+};
 
 #[derive(Clone, Debug, Default)]
 pub struct ParamNames {
@@ -11,35 +17,150 @@ impl ParamNames {
     pub fn position(&self, name: &str) -> Option<usize> {
         self.names.iter().position(|n| *n == name.as_bytes())
     }
+
+    /// Returns the name of the parameter at the specified position.
+    pub fn name(&self, i: usize) -> Option<&str> {
+        self.names
+            .get(i)
+            .map(|name| std::str::from_utf8(name).expect("parameter names are ASCII"))
+    }
 }
 
 /// A set of captured parameter values from an HTTP path.
+///
+/// The values are kept as raw bytes, since the path recognized by
+/// `Router::recognize_bytes` may not be valid UTF-8. The accessors returning
+/// `&str` check the encoding of each value, and treat the values that are not
+/// valid UTF-8 as absent. Use the `*_bytes` accessors to get the raw values.
 #[derive(Debug, Clone)]
 pub struct Params<'r> {
-    pub(crate) path: Cow<'r, str>,
+    pub(crate) path: Cow<'r, [u8]>,
     pub(crate) names: Cow<'r, ParamNames>,
-    pub(crate) spans: Cow<'r, Vec<(usize, usize)>>,
+    pub(crate) spans: Cow<'r, [(usize, usize)]>,
     pub(crate) wildcard: Option<(usize, usize)>,
 }
 
 impl<'r> Params<'r> {
+    /// Returns the number of captured parameters, including the wildcard.
+    ///
+    /// The values that are not valid UTF-8 are counted as well, so this may be
+    /// larger than `self.iter().count()`, but always equals `self.names().count()`.
+    pub fn len(&self) -> usize {
+        self.spans.len() + self.get_wildcard_bytes().map_or(0, |_| 1)
+    }
+
+    /// Returns `true` if no parameter is captured.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the names of the captured parameters.
+    ///
+    /// The wildcard parameter, if captured, comes last with the name `"*"`.
+    /// Unlike `iter`, the parameters whose values are not valid UTF-8 are included.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        (0..self.spans.len())
+            .filter_map(move |i| self.names.name(i))
+            .chain(self.get_wildcard_bytes().map(|_| "*"))
+    }
+
+    /// Returns an iterator over the pairs of the name and value of the captured parameters.
+    ///
+    /// The wildcard parameter, if captured, comes last with the name `"*"`.
+    /// The parameters whose values are not valid UTF-8 are skipped, so the
+    /// number of items may be smaller than `len`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        (0..self.spans.len())
+            .filter_map(move |i| Some((self.names.name(i)?, self.get(i)?)))
+            .chain(self.get_wildcard().map(|wildcard| ("*", wildcard)))
+    }
+
     /// Finds a parameter value by position.
     pub fn get(&self, i: usize) -> Option<&str> {
-        self.spans.get(i).map(|&(s, e)| &self.path[s..e])
+        self.get_bytes(i)
+            .and_then(|value| str::from_utf8(value).ok())
+    }
+
+    /// Finds the raw bytes of a parameter value by position.
+    pub fn get_bytes(&self, i: usize) -> Option<&[u8]> {
+        self.spans.get(i).and_then(|&(s, e)| self.path.get(s..e))
+    }
+
+    /// Returns the byte range of the parameter value at the specified position in the path.
+    pub fn span(&self, i: usize) -> Option<Range<usize>> {
+        self.spans.get(i).map(|&(s, e)| s..e)
     }
 
     /// Finds a parameter value by name.
     pub fn name(&self, name: &str) -> Option<&str> {
+        self.name_bytes(name)
+            .and_then(|value| str::from_utf8(value).ok())
+    }
+
+    /// Finds the raw bytes of a parameter value by name.
+    pub fn name_bytes(&self, name: &str) -> Option<&[u8]> {
         match name {
-            "*" => self.get_wildcard(),
-            name => self.names.position(name).and_then(|i| self.get(i)),
+            "*" => self.get_wildcard_bytes(),
+            name => self.names.position(name).and_then(|i| self.get_bytes(i)),
         }
     }
 
+    /// Parses the value of the parameter with the specified name.
+    ///
+    /// The wildcard parameter can be specified with the name `"*"`.
+    pub fn parse<T>(&self, name: &str) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let position = match name {
+            "*" => None,
+            name => self.names.position(name),
+        };
+        let value = self
+            .name_bytes(name)
+            .ok_or_else(|| ParamError::missing(Some(name), position))?;
+        let value = to_str(value, Some(name), position)?;
+        value
+            .parse()
+            .map_err(|err| ParamError::parse(Some(name), position, value, err))
+    }
+
+    /// Parses the value of the parameter at the specified position.
+    pub fn parse_at<T>(&self, i: usize) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let name = self.names.name(i);
+        let value = self
+            .get_bytes(i)
+            .ok_or_else(|| ParamError::missing(name, Some(i)))?;
+        let value = to_str(value, name, Some(i))?;
+        value
+            .parse()
+            .map_err(|err| ParamError::parse(name, Some(i), value, err))
+    }
+
     /// Returns the value of extracted wildcard parameter if possible.
     pub fn get_wildcard(&self) -> Option<&str> {
+        self.get_wildcard_bytes()
+            .and_then(|value| str::from_utf8(value).ok())
+    }
+
+    /// Returns the raw bytes of extracted wildcard parameter if possible.
+    pub fn get_wildcard_bytes(&self) -> Option<&[u8]> {
+        if self.names.has_wildcard {
+            self.wildcard.and_then(|(s, e)| self.path.get(s..e))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the byte range of extracted wildcard parameter in the path, if possible.
+    pub fn wildcard_span(&self) -> Option<Range<usize>> {
         if self.names.has_wildcard {
-            self.wildcard.map(|(s, e)| &self.path[s..e])
+            self.wildcard.map(|(s, e)| s..e)
         } else {
             None
         }
@@ -56,6 +177,16 @@ impl<'r> Params<'r> {
     }
 }
 
+/// Converts a raw parameter value to a string, reporting invalid UTF-8 as a parse error.
+fn to_str<'a>(
+    value: &'a [u8],
+    name: Option<&str>,
+    position: Option<usize>,
+) -> Result<&'a str, ParamError> {
+    str::from_utf8(value)
+        .map_err(|err| ParamError::parse(name, position, &String::from_utf8_lossy(value), err))
+}
+
 impl<'r> std::ops::Index<usize> for Params<'r> {
     type Output = str;
 
@@ -72,3 +203,21 @@ impl<'r, 's> std::ops::Index<&'s str> for Params<'r> {
             .unwrap_or_else(|| panic!("invalid param name"))
     }
 }
+
+impl<'r, 'a> From<&'a Params<'r>> for HashMap<String, String> {
+    fn from(params: &'a Params<'r>) -> Self {
+        params
+            .iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+}
+
+impl<'r, 'a> From<&'a Params<'r>> for Vec<(String, String)> {
+    fn from(params: &'a Params<'r>) -> Self {
+        params
+            .iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+}
@@ -0,0 +1,105 @@
+//! Parsing of query strings.
+
+use std::{iter::FusedIterator, str::Split};
+
+/// An iterator over the key-value pairs in a query string.
+///
+/// The keys and values are borrowed from the query string as they are, without
+/// percent-decoding. Empty pairs are skipped, and a key without `=` yields an
+/// empty value.
+#[derive(Debug, Clone)]
+pub struct Query<'a> {
+    pairs: Split<'a, char>,
+}
+
+impl<'a> Query<'a> {
+    pub(crate) fn new(query: &'a str) -> Self {
+        Self {
+            pairs: query.split('&'),
+        }
+    }
+
+    /// Returns the value of the first pair with the specified key.
+    pub fn get(self, key: &str) -> Option<&'a str> {
+        self.into_iter().find(|&(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns whether a pair with the specified key exists.
+    pub fn contains_key(self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+impl<'a> Iterator for Query<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let pair = self.pairs.next()?;
+            if pair.is_empty() {
+                continue;
+            }
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next().unwrap_or("");
+            return Some((key, kv.next().unwrap_or("")));
+        }
+    }
+}
+
+impl<'a> FusedIterator for Query<'a> {}
+
+/// Splits the query string and the fragment off the provided request target.
+pub(crate) fn split_path(target: &str) -> (&str, Option<&str>) {
+    let target = match target.find('#') {
+        Some(pos) => &target[..pos],
+        None => target,
+    };
+    match target.find('?') {
+        Some(pos) => (&target[..pos], Some(&target[pos + 1..])),
+        None => (target, None),
+    }
+}
+
+/// Splits the query string and the fragment off the provided raw request target.
+pub(crate) fn split_target(target: &[u8]) -> (&[u8], Option<&[u8]>) {
+    let target = match target.iter().position(|&c| c == b'#') {
+        Some(pos) => &target[..pos],
+        None => target,
+    };
+    match target.iter().position(|&c| c == b'?') {
+        Some(pos) => (&target[..pos], Some(&target[pos + 1..])),
+        None => (target, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs() {
+        let query = Query::new("a=1&b&&c=x=y&a=2");
+        assert_eq!(
+            query.clone().collect::<Vec<_>>(),
+            vec![("a", "1"), ("b", ""), ("c", "x=y"), ("a", "2")]
+        );
+        assert_eq!(query.clone().get("a"), Some("1"));
+        assert_eq!(query.clone().get("b"), Some(""));
+        assert!(!query.contains_key("d"));
+        assert_eq!(Query::new("").count(), 0);
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(split_path("/posts/12"), ("/posts/12", None));
+        assert_eq!(split_path("/posts/12?x=1"), ("/posts/12", Some("x=1")));
+        assert_eq!(split_path("/posts/12?x=1#top"), ("/posts/12", Some("x=1")));
+        assert_eq!(split_path("/posts/12#top?x"), ("/posts/12", None));
+        assert_eq!(split_path("/posts/12?"), ("/posts/12", Some("")));
+
+        assert_eq!(
+            split_target(b"/posts/\xff?x=1#top"),
+            (&b"/posts/\xff"[..], Some(&b"x=1"[..]))
+        );
+    }
+}
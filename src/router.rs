@@ -1,20 +1,52 @@
 use crate::{
-    endpoint::{Endpoint, EndpointId}, //
+    endpoint::{Endpoint, EndpointId, EndpointKind}, //
     error::Result,
+    explain::{Explain, Outcome, Rejection, Step},
+    guard::{Guard, Input},
     param::{ParamNames, Params},
+    query::{self, Query},
     tree::Tree,
+    version::{self, VersionSource},
 };
 use indexmap::IndexMap;
 use std::{
     borrow::Cow,
-    ops::{Index, IndexMut},
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    ops::{Index, IndexMut, RangeBounds, RangeInclusive},
+    sync::Arc,
 };
 
 /// An HTTP router.
+///
+/// Cloning a router is cheap, since the routing tree and the endpoints are
+/// shared with the clone. Modifying either router afterwards only copies the
+/// affected parts, i.e. the nodes along the inserted path and the modified
+/// endpoints, and never the data associated with the endpoints.
 #[derive(Debug)]
 pub struct Router<T> {
-    tree: Tree,
-    endpoints: IndexMap<EndpointId, Endpoint<T>>,
+    pub(crate) tree: Tree,
+    endpoints: IndexMap<EndpointId, Arc<Endpoint<T>>>,
+    named: HashMap<String, EndpointId>,
+    fallback: Option<EndpointId>,
+    scope_fallbacks: HashMap<EndpointId, EndpointId>,
+    pub(crate) version_source: Option<VersionSource>,
+    extensions: Vec<String>,
+}
+
+impl<T> Clone for Router<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+            endpoints: self.endpoints.clone(),
+            named: self.named.clone(),
+            fallback: self.fallback,
+            scope_fallbacks: self.scope_fallbacks.clone(),
+            version_source: self.version_source.clone(),
+            extensions: self.extensions.clone(),
+        }
+    }
 }
 
 impl<T> Default for Router<T> {
@@ -22,6 +54,11 @@ impl<T> Default for Router<T> {
         Self {
             tree: Tree::default(),
             endpoints: IndexMap::new(),
+            named: HashMap::new(),
+            fallback: None,
+            scope_fallbacks: HashMap::new(),
+            version_source: None,
+            extensions: vec![],
         }
     }
 }
@@ -33,21 +70,87 @@ impl<T> Router<T> {
     }
 
     /// Adds a route to this router.
+    ///
+    /// Several routes may be added with the same path. They are tried in the
+    /// order of registration, and the first one whose methods, host and guards
+    /// accept the request is selected.
     pub fn add_route(&mut self, path: &str, data: T) -> Result<EndpointId> {
-        let id = EndpointId(self.endpoints.len());
+        self.add_endpoint(EndpointKind::Route, path, data)
+    }
+
+    /// Adds a route that is available in the specified range of API versions.
+    ///
+    /// Several versioned routes may share the same path. For a requested version,
+    /// the route whose range contains the version and starts the latest is used,
+    /// so a route registered for `1..` is inherited by every later version until
+    /// it is redefined, e.g. by a route registered for `3..`.
+    /// See `Router::set_version_source` for how the version is determined.
+    pub fn add_versioned<R>(&mut self, path: &str, versions: R, data: T) -> Result<EndpointId>
+    where
+        R: RangeBounds<u32>,
+    {
+        let versions = version::to_inclusive(versions)?;
+        let id = self.next_id();
+        self.insert_versioned(id, path, versions, data)
+    }
 
+    pub(crate) fn insert_versioned(
+        &mut self,
+        id: EndpointId,
+        path: &str,
+        versions: RangeInclusive<u32>,
+        data: T,
+    ) -> Result<EndpointId> {
         let mut names = None;
-        let leaf = self.tree.insert(path.as_ref(), &mut names)?;
-        leaf.route = Some(id);
+        let endpoints = &self.endpoints;
+        let node = self.tree.insert(path.as_ref(), &mut names)?;
+        for other in node.routes.iter().map(|id| &endpoints[id]) {
+            match other.versions {
+                None => {
+                    bail!(format!(
+                        "the path `{}` is already used by an unversioned route",
+                        path
+                    ));
+                }
+                Some(ref v) if v.start() == versions.start() => {
+                    bail!(format!(
+                        "the route `{}` is already defined for version {}",
+                        other.path,
+                        versions.start()
+                    ));
+                }
+                Some(..) => (),
+            }
+        }
+
+        // The alternates are ordered from the latest to the earliest version,
+        // so that the first accepted one is the most recent.
+        let pos = node
+            .routes
+            .iter()
+            .position(|id| {
+                endpoints[id]
+                    .versions
+                    .as_ref()
+                    .is_some_and(|v| v.start() < versions.start())
+            })
+            .unwrap_or(node.routes.len());
+        node.routes.insert(pos, id);
 
         self.endpoints.insert(
             id,
-            Endpoint {
+            Arc::new(Endpoint {
                 id,
+                kind: EndpointKind::Route,
                 path: path.to_owned(),
+                name: None,
+                methods: vec![],
+                host: None,
+                versions: Some(versions),
+                guards: vec![],
                 names,
-                data,
-            },
+                data: Arc::new(data),
+            }),
         );
 
         Ok(id)
@@ -55,45 +158,503 @@ impl<T> Router<T> {
 
     /// Adds a scope to this router.
     pub fn add_scope(&mut self, path: &str, data: T) -> Result<EndpointId> {
-        let id = EndpointId(self.endpoints.len());
+        self.add_endpoint(EndpointKind::Scope, path, data)
+    }
 
+    /// Sets the fallback of this router.
+    ///
+    /// The fallback is reported by `Recognize::fallback` when no route matches
+    /// the path and no enclosing scope has its own fallback.
+    pub fn set_fallback(&mut self, data: T) -> Result<EndpointId> {
+        let id = self.next_id();
+        self.insert_fallback(id, data)
+    }
+
+    pub(crate) fn insert_fallback(&mut self, id: EndpointId, data: T) -> Result<EndpointId> {
+        if self.fallback.is_some() {
+            bail!("the fallback is already set");
+        }
+        let id = self.insert_endpoint(id, EndpointKind::Fallback, "*", data)?;
+        self.fallback = Some(id);
+        Ok(id)
+    }
+
+    /// Sets the fallback of the scope with the specified ID.
+    ///
+    /// The fallback is reported by `Recognize::fallback` when no route matches
+    /// a path within the scope and no inner scope has its own fallback.
+    pub fn set_scope_fallback(&mut self, scope: EndpointId, data: T) -> Result<EndpointId> {
+        let id = self.next_id();
+        self.insert_scope_fallback(id, scope, data)
+    }
+
+    pub(crate) fn insert_scope_fallback(
+        &mut self,
+        id: EndpointId,
+        scope: EndpointId,
+        data: T,
+    ) -> Result<EndpointId> {
+        let path = match self.endpoints.get(&scope) {
+            Some(endpoint) if endpoint.kind == EndpointKind::Scope => endpoint.path.clone(),
+            _ => {
+                bail!("invalid scope ID");
+            }
+        };
+        if self.scope_fallbacks.contains_key(&scope) {
+            bail!(format!("the fallback of scope `{}` is already set", path));
+        }
+        let id = self.insert_endpoint(id, EndpointKind::Fallback, &path, data)?;
+        self.scope_fallbacks.insert(scope, id);
+        Ok(id)
+    }
+
+    fn add_endpoint(&mut self, kind: EndpointKind, path: &str, data: T) -> Result<EndpointId> {
+        let id = self.next_id();
+        self.insert_endpoint(id, kind, path, data)
+    }
+
+    fn next_id(&self) -> EndpointId {
+        EndpointId(self.endpoints.len())
+    }
+
+    /// Inserts an endpoint with the specified ID, which must not be used yet.
+    pub(crate) fn insert_endpoint(
+        &mut self,
+        id: EndpointId,
+        kind: EndpointKind,
+        path: &str,
+        data: T,
+    ) -> Result<EndpointId> {
         let mut names = None;
-        let leaf = self.tree.insert(path.as_ref(), &mut names)?;
-        leaf.scope = Some(id);
+        match kind {
+            EndpointKind::Route => {
+                let endpoints = &self.endpoints;
+                let node = self.tree.insert(path.as_ref(), &mut names)?;
+                if node
+                    .routes
+                    .iter()
+                    .any(|id| endpoints[id].versions.is_some())
+                {
+                    bail!(format!(
+                        "the path `{}` is already used by a versioned route",
+                        path
+                    ));
+                }
+                node.routes.push(id);
+            }
+            EndpointKind::Scope => self.tree.insert(path.as_ref(), &mut names)?.scope = Some(id),
+            EndpointKind::Fallback => (),
+        }
 
         self.endpoints.insert(
             id,
-            Endpoint {
+            Arc::new(Endpoint {
                 id,
+                kind,
                 path: path.to_owned(),
+                name: None,
+                methods: vec![],
+                host: None,
+                versions: None,
+                guards: vec![],
                 names,
-                data,
-            },
+                data: Arc::new(data),
+            }),
         );
 
         Ok(id)
     }
 
+    /// Associates a name with the endpoint with the specified ID.
+    ///
+    /// The name must be unique within this router.
+    pub fn set_name(&mut self, id: EndpointId, name: &str) -> Result<()> {
+        if !self.endpoints.contains_key(&id) {
+            bail!("invalid endpoint ID");
+        }
+        if self.named.get(name).is_some_and(|&other| other != id) {
+            bail!(format!("the endpoint name `{}` is already used", name));
+        }
+
+        let endpoint = Arc::make_mut(&mut self.endpoints[&id]);
+        if let Some(old) = endpoint.name.replace(name.to_owned()) {
+            self.named.remove(&old);
+        }
+        self.named.insert(name.to_owned(), id);
+
+        Ok(())
+    }
+
+    /// Sets the list of HTTP methods accepted by the endpoint with the specified ID.
+    pub fn set_methods<I>(&mut self, id: EndpointId, methods: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let endpoint = self.endpoint_mut(id).ok_or("invalid endpoint ID")?;
+        endpoint.methods = methods.into_iter().map(Into::into).collect();
+        Ok(())
+    }
+
+    /// Restricts the endpoint with the specified ID to the provided host name.
+    ///
+    /// The restriction is taken into account by `Router::recognize_request`.
+    /// An IPv6 address may be specified with or without brackets, e.g. `::1`
+    /// or `[::1]`.
+    pub fn set_host(&mut self, id: EndpointId, host: &str) -> Result<()> {
+        let endpoint = self.endpoint_mut(id).ok_or("invalid endpoint ID")?;
+        endpoint.host = Some(host.to_owned());
+        Ok(())
+    }
+
+    /// Attaches a guard to the endpoint with the specified ID.
+    ///
+    /// The route is only selected if all of its guards accept the request.
+    /// See the [`guard`](guard/index.html) module for details.
+    pub fn add_guard<G>(&mut self, id: EndpointId, guard: G) -> Result<()>
+    where
+        G: Guard,
+    {
+        let endpoint = self.endpoint_mut(id).ok_or("invalid endpoint ID")?;
+        endpoint.guards.push(Arc::new(guard));
+        Ok(())
+    }
+
+    /// Registers a file extension that may be appended to the paths of the routes.
+    ///
+    /// If the last segment of a path ends with `.<extension>`, the full path is
+    /// recognized first, so that a route spelling out the extension, such as
+    /// `/files/report.json`, takes precedence. If no route matches, or if the
+    /// extension was captured by a parameter or a wildcard, the path is then
+    /// recognized without the extension, which is reported by
+    /// `Recognize::extension`. The longest registered extension is tried first.
+    ///
+    /// ```
+    /// # use tsukuyomi_router::Router;
+    /// let mut router = Router::new();
+    /// router.add_route("/reports/:id", "report")?;
+    /// router.add_extension("json")?;
+    ///
+    /// let recognize = router.recognize("/reports/42.json");
+    /// let (_, params) = recognize.route().unwrap();
+    /// assert_eq!(params.unwrap().get(0), Some("42"));
+    /// assert_eq!(recognize.extension(), Some("json"));
+    /// # Ok::<(), tsukuyomi_router::Error>(())
+    /// ```
+    pub fn add_extension(&mut self, extension: &str) -> Result<()> {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        if extension.is_empty() || extension.contains('/') {
+            bail!(format!("invalid extension `{}`", extension));
+        }
+        if !self.extensions.iter().any(|e| e == extension) {
+            let pos = self
+                .extensions
+                .iter()
+                .position(|e| e.len() < extension.len())
+                .unwrap_or(self.extensions.len());
+            self.extensions.insert(pos, extension.to_owned());
+        }
+        Ok(())
+    }
+
+    /// Returns the file extensions registered by `Router::add_extension`.
+    pub fn extensions(&self) -> impl Iterator<Item = &str> + '_ {
+        self.extensions.iter().map(String::as_str)
+    }
+
+    /// Returns an iterator over all endpoints, in the order of registration.
+    pub fn endpoints(&self) -> impl Iterator<Item = &Endpoint<T>> + '_ {
+        self.endpoints.values().map(|endpoint| &**endpoint)
+    }
+
+    /// Returns the routes in this router, sorted by their paths.
+    pub fn routes(&self) -> Vec<&Endpoint<T>> {
+        let mut routes: Vec<_> = self
+            .endpoints()
+            .filter(|endpoint| endpoint.kind == EndpointKind::Route)
+            .collect();
+        routes.sort_by(|a, b| a.path.cmp(&b.path));
+        routes
+    }
+
+    /// Returns a value that renders the internal routing tree in a human-readable form.
+    ///
+    /// Each line corresponds to a node in the tree, indented by its depth. Parameter,
+    /// constrained parameter and wildcard nodes are shown as `:name`, `:name<regex>`
+    /// and `*<slug>` respectively, and are listed in the order they are tried during
+    /// recognition. The name of a parameter is taken from the endpoints below the
+    /// node, and the names are separated by `|` if these endpoints disagree.
+    pub fn print_tree(&self) -> PrintTree<'_, T> {
+        PrintTree { router: self }
+    }
+
+    /// Exports the internal routing tree as a Graphviz DOT graph.
+    ///
+    /// Static, parameter (constrained or not) and wildcard edges are drawn as solid, dashed and dotted
+    /// lines respectively, and the nodes that terminate a route or scope are
+    /// labeled with the path of the corresponding endpoint.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        self.tree
+            .write_dot(&mut dot, &|id| self.endpoint(id).map(|e| e.path()))
+            .expect("writing to a String never fails");
+        dot
+    }
+
+    /// Returns a reference to the endpoint with the specified name.
+    pub fn endpoint_by_name(&self, name: &str) -> Option<&Endpoint<T>> {
+        self.named.get(name).and_then(|&id| self.endpoint(id))
+    }
+
     /// Returns a reference to the endpoint with the specified ID.
     pub fn endpoint(&self, id: EndpointId) -> Option<&Endpoint<T>> {
-        self.endpoints.get(&id)
+        self.endpoints.get(&id).map(|endpoint| &**endpoint)
     }
 
     /// Returns a mutable reference to the endpoint with the specified ID.
+    ///
+    /// If the endpoint is shared with a clone of this router, it is copied
+    /// first. The associated data remains shared.
     pub fn endpoint_mut(&mut self, id: EndpointId) -> Option<&mut Endpoint<T>> {
-        self.endpoints.get_mut(&id)
+        self.endpoints.get_mut(&id).map(Arc::make_mut)
     }
 
     /// Searches for the route(s) matching the provided path.
+    ///
+    /// The query string and the fragment, if any, are split off the path
+    /// before matching. The query string is available via `Recognize::query`.
+    ///
+    /// If the version source is `VersionSource::PathPrefix`, a leading `/v<N>`
+    /// segment selects the version of the versioned routes and is not matched
+    /// against the routes. If the rest of the path does not match a versioned
+    /// route, the path is recognized as it is, including the leading segment.
+    ///
+    /// The guards of the routes are evaluated against an `Input` that only
+    /// consists of the path and the query string. In particular, a route with
+    /// a guard inspecting the headers is never returned by this method. Use
+    /// `Router::recognize_input` or `Router::recognize_request` instead.
     pub fn recognize<'r>(&'r self, path: &'r str) -> Recognize<'r, T> {
-        let recognize = self.tree.recognize(path.as_ref());
+        self.recognize_input(&Input::new(path))
+    }
+
+    /// Searches for the route(s) matching the provided raw request target.
+    ///
+    /// Unlike `recognize`, the target is not required to be valid UTF-8, and
+    /// the values of the parameters are available as bytes via `Params::get_bytes`.
+    /// If the target is valid UTF-8, this is equivalent to `recognize`.
+    /// Otherwise, the version prefix and the file extensions are not considered,
+    /// the query string is ignored unless it is valid UTF-8, and the guards are
+    /// evaluated against an input with an empty path.
+    pub fn recognize_bytes<'r>(&'r self, target: &'r [u8]) -> Recognize<'r, T> {
+        if let Ok(target) = std::str::from_utf8(target) {
+            return self.recognize(target);
+        }
+
+        let (path, query) = query::split_target(target);
+        let mut input = Input::new("");
+        if let Some(query) = query.and_then(|query| std::str::from_utf8(query).ok()) {
+            input = input.with_query(query);
+        }
+        self.recognize_path(path, None, &input, None)
+    }
+
+    /// Searches for the route(s) matching the provided request information.
+    ///
+    /// In addition to the path, the versions and guards of the routes are
+    /// checked. If the input has a method, the methods and host registered by
+    /// `Router::set_methods` and `Router::set_host` are checked as well, and
+    /// if a route only rejects the request because of its method,
+    /// `Recognize::is_method_not_allowed` returns `true`.
+    pub fn recognize_input<'r>(&'r self, input: &Input<'r>) -> Recognize<'r, T> {
+        self.recognize_traced(input, None)
+    }
+
+    fn recognize_traced<'r>(
+        &'r self,
+        input: &Input<'r>,
+        mut trace: Option<&mut Vec<Step>>,
+    ) -> Recognize<'r, T> {
+        let (path, version) = self.requested_version(input);
+        if self.version_source != Some(VersionSource::PathPrefix) || version.is_none() {
+            return self.recognize_with_extension(path, version, input, trace);
+        }
+
+        // The version prefix is only stripped for the versioned routes, so that
+        // the other routes starting with a segment like `/v1` remain reachable.
+        let recognize = self.recognize_with_extension(path, version, input, trace.as_deref_mut());
+        if recognize
+            .route
+            .is_some_and(|route| route.versions.is_some())
+        {
+            return recognize;
+        }
+        self.recognize_with_extension(input.path(), None, input, trace)
+    }
+
+    fn recognize_with_extension<'r>(
+        &'r self,
+        path: &'r str,
+        version: Option<u32>,
+        input: &Input<'r>,
+        mut trace: Option<&mut Vec<Step>>,
+    ) -> Recognize<'r, T> {
+        let recognize = self.recognize_path(path.as_bytes(), version, input, trace.as_deref_mut());
+        let (stripped, extension) = match self.split_extension(path) {
+            Some(split) => split,
+            None => return recognize,
+        };
+        if recognize.route.is_some() && !recognize.captures_from(stripped.len()) {
+            return recognize;
+        }
+
+        let mut stripped = self.recognize_path(stripped.as_bytes(), version, input, trace);
+        if stripped.route.is_some() {
+            stripped.extension = Some(extension);
+            return stripped;
+        }
+        recognize
+    }
+
+    fn split_extension<'p>(&self, path: &'p str) -> Option<(&'p str, &'p str)> {
+        let segment_start = path.rfind('/').map_or(0, |pos| pos + 1);
+        self.extensions.iter().find_map(|extension| {
+            let pos = path.len().checked_sub(extension.len() + 1)?;
+            if pos > segment_start
+                && path.ends_with(extension.as_str())
+                && path[pos..].starts_with('.')
+            {
+                Some((&path[..pos], &path[pos + 1..]))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the reason why the endpoint rejects the input, if any.
+    ///
+    /// The method is checked last, so that `Rejection::Method` is only
+    /// returned if the endpoint accepts everything else.
+    fn rejection(
+        &self,
+        endpoint: &Endpoint<T>,
+        version: Option<u32>,
+        input: &Input<'_>,
+    ) -> Option<Rejection> {
+        let version_matches = endpoint
+            .versions
+            .as_ref()
+            .zip(version)
+            .map_or(true, |(versions, version)| versions.contains(&version));
+        if !version_matches {
+            Some(Rejection::Version)
+        } else if input.method().is_some() && !endpoint.matches_host(input.host()) {
+            Some(Rejection::Host)
+        } else if !endpoint.guards.iter().all(|guard| guard.check(input)) {
+            Some(Rejection::Guard)
+        } else if input.method().is_some_and(|m| !endpoint.allows_method(m)) {
+            Some(Rejection::Method)
+        } else {
+            None
+        }
+    }
+
+    fn recognize_path<'r>(
+        &'r self,
+        path: &'r [u8],
+        version: Option<u32>,
+        input: &Input<'r>,
+        trace: Option<&mut Vec<Step>>,
+    ) -> Recognize<'r, T> {
+        let is_request = input.method().is_some();
+
+        // The routes rejected only because of the method are recorded during
+        // the search, so that the path does not need to be traversed again.
+        let allowed_methods = RefCell::new(Vec::<&'r str>::new());
+        let reject = |id: EndpointId| {
+            let endpoint = &self.endpoints[&id];
+            let rejection = self.rejection(endpoint, version, input);
+            if rejection == Some(Rejection::Method) {
+                let mut allowed_methods = allowed_methods.borrow_mut();
+                for method in &endpoint.methods {
+                    if !allowed_methods.contains(&method.as_str()) {
+                        allowed_methods.push(method);
+                    }
+                }
+            }
+            rejection
+        };
+        let recognize = match trace {
+            Some(trace) => {
+                trace.push(Step::Start {
+                    path: String::from_utf8_lossy(path).into_owned(),
+                    version,
+                });
+                self.tree.recognize_traced(path, &reject, trace)
+            }
+            None => self.tree.recognize_with(path, &|id| reject(id).is_none()),
+        };
+        let allowed_methods = match recognize.route {
+            Some(..) => vec![],
+            None => allowed_methods.into_inner(),
+        };
+
+        let route = recognize.route.map(|id| &*self.endpoints[&id]);
+        let version = version.or_else(|| route?.versions.as_ref().map(|v| *v.start()));
 
         Recognize {
-            route: recognize.route.and_then(|id| self.endpoints.get(&id)),
-            scope: recognize.scope.and_then(|id| self.endpoints.get(&id)),
+            router: self,
+            route,
+            scopes: recognize
+                .scopes
+                .iter()
+                .filter_map(|&id| self.endpoint(id))
+                .filter(|scope| !is_request || scope.matches_host(input.host()))
+                .collect(),
             path,
             params: recognize.params,
             wildcard: recognize.wildcard,
+            offset: recognize.offset,
+            version,
+            extension: None,
+            query: input.query(),
+            allowed_methods,
+        }
+    }
+
+    /// Recognizes the provided path while recording every step taken by the router.
+    ///
+    /// This is intended for debugging paths that do not match the expected route.
+    /// The path goes through the same steps as in `recognize`, including the
+    /// version prefix and the file extensions, and the routes rejected by their
+    /// versions or guards are reported.
+    pub fn explain<'r>(&'r self, path: &'r str) -> Explain<'r, T> {
+        self.explain_input(&Input::new(path))
+    }
+
+    /// Recognizes the provided request information while recording every step
+    /// taken by the router.
+    ///
+    /// See `Router::explain` and `Router::recognize_input`.
+    pub fn explain_input<'r>(&'r self, input: &Input<'r>) -> Explain<'r, T> {
+        let mut steps = vec![];
+        let recognize = self.recognize_traced(input, Some(&mut steps));
+        // The candidate paths are slices of the input path.
+        let candidate = std::str::from_utf8(recognize.path).unwrap_or_default();
+
+        let outcome = match recognize.route {
+            Some(route) => Outcome::Matched(route.id()),
+            None if recognize.offset == candidate.len() => Outcome::NoRoute,
+            None => Outcome::Unmatched {
+                offset: recognize.offset,
+            },
+        };
+
+        Explain {
+            router: self,
+            path: input.path(),
+            candidate,
+            steps,
+            outcome,
         }
     }
 }
@@ -114,14 +675,36 @@ impl<T> IndexMut<EndpointId> for Router<T> {
     }
 }
 
+/// A value that renders the routing tree of `Router`.
+///
+/// This value is created by `Router::print_tree`.
+#[derive(Debug)]
+pub struct PrintTree<'r, T> {
+    router: &'r Router<T>,
+}
+
+impl<'r, T> fmt::Display for PrintTree<'r, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.router
+            .tree
+            .print(f, &|id| self.router.endpoint(id).map(|e| e.path()))
+    }
+}
+
 /// A value that contains the recognition result of the router.
 #[derive(Debug)]
 pub struct Recognize<'r, T> {
-    route: Option<&'r Endpoint<T>>,
-    scope: Option<&'r Endpoint<T>>,
-    path: &'r str,
-    params: Vec<(usize, usize)>,
-    wildcard: Option<(usize, usize)>,
+    pub(crate) router: &'r Router<T>,
+    pub(crate) route: Option<&'r Endpoint<T>>,
+    pub(crate) scopes: Vec<&'r Endpoint<T>>,
+    pub(crate) path: &'r [u8],
+    pub(crate) params: Vec<(usize, usize)>,
+    pub(crate) wildcard: Option<(usize, usize)>,
+    pub(crate) offset: usize,
+    pub(crate) version: Option<u32>,
+    pub(crate) extension: Option<&'r str>,
+    pub(crate) query: Option<&'r str>,
+    pub(crate) allowed_methods: Vec<&'r str>,
 }
 
 impl<'r, T> Recognize<'r, T> {
@@ -132,19 +715,134 @@ impl<'r, T> Recognize<'r, T> {
         Some((route, params))
     }
 
-    /// Returns a reference to the matched scope if possible.
+    /// Returns a reference to the innermost matched scope if possible.
+    ///
+    /// The parameters of a scope are the leading parameters of the path, up
+    /// to the end of the scope's own path. The wildcard parameter is only
+    /// included if the path of the scope contains a wildcard.
     pub fn scope(&self) -> Option<(&Endpoint<T>, Option<Params<'_>>)> {
-        let scope = self.scope?;
-        let params = scope.names.as_ref().map(|names| self.new_params(names));
-        Some((scope, params))
+        let scope = *self.scopes.last()?;
+        Some((scope, self.new_scope_params(scope)))
+    }
+
+    /// Returns the parameters captured by the matched scope with the specified ID.
+    ///
+    /// This returns `None` if the scope did not match or has no parameters.
+    pub fn scope_params(&self, id: EndpointId) -> Option<Params<'_>> {
+        let scope = self.scopes.iter().find(|scope| scope.id == id)?;
+        self.new_scope_params(scope)
+    }
+
+    /// Returns the outermost matched scope that captures the route parameter with the specified name.
+    ///
+    /// The wildcard parameter can be specified with the name `"*"`. This
+    /// returns `None` if the parameter belongs to the route alone.
+    ///
+    /// ```
+    /// # use tsukuyomi_router::Router;
+    /// let mut router = Router::new();
+    /// router.add_scope("/orgs/:org/", "org")?;
+    /// router.add_route("/orgs/:org/repos/:repo", "repo")?;
+    ///
+    /// let recognize = router.recognize("/orgs/rust-lang/repos/cargo");
+    /// assert_eq!(recognize.param_scope("org").map(|s| *s.data()), Some("org"));
+    /// assert!(recognize.param_scope("repo").is_none());
+    /// # Ok::<(), tsukuyomi_router::Error>(())
+    /// ```
+    pub fn param_scope(&self, name: &str) -> Option<&Endpoint<T>> {
+        let names = self.route?.names.as_ref()?;
+        let position = match name {
+            "*" if names.has_wildcard => None,
+            name => Some(names.position(name)?),
+        };
+        self.scopes.iter().copied().find(|scope| {
+            scope.names.as_ref().is_some_and(|names| match position {
+                Some(i) => i < names.names.len(),
+                None => names.has_wildcard,
+            })
+        })
+    }
+
+    /// Returns an iterator over the matched scopes, from the outermost to the innermost.
+    pub fn scopes(&self) -> impl Iterator<Item = &Endpoint<T>> + '_ {
+        self.scopes.iter().copied()
+    }
+
+    /// Returns the fallback that applies to the path if no route matched.
+    ///
+    /// The fallback is resolved from the innermost matched scope outwards,
+    /// and then the fallback of the router is used.
+    pub fn fallback(&self) -> Option<&Endpoint<T>> {
+        if self.route.is_some() {
+            return None;
+        }
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| self.router.scope_fallbacks.get(&scope.id))
+            .or(self.router.fallback.as_ref())
+            .and_then(|id| self.router.endpoint(*id))
+    }
+
+    /// Returns the API version that the recognition was resolved against.
+    ///
+    /// This is the version requested by the client if any. Otherwise, if the
+    /// matched route is versioned, it is the first version of the latest route.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// Returns an iterator over the pairs in the query string of the recognized target.
+    pub fn query(&self) -> Query<'r> {
+        Query::new(self.query.unwrap_or(""))
+    }
+
+    /// Returns the file extension that was stripped from the path, if any.
+    ///
+    /// See `Router::add_extension`.
+    pub fn extension(&self) -> Option<&'r str> {
+        self.extension
+    }
+
+    /// Returns whether the path matched a route that does not accept the requested method.
+    ///
+    /// This flag is only set by `Router::recognize_input` and `Router::recognize_request`.
+    pub fn is_method_not_allowed(&self) -> bool {
+        !self.allowed_methods.is_empty()
+    }
+
+    /// Returns the methods accepted by the routes that only rejected the requested method.
+    ///
+    /// The list is empty unless `Recognize::is_method_not_allowed` returns `true`,
+    /// and is suitable for the `Allow` header of a `405 Method Not Allowed` response.
+    pub fn allowed_methods(&self) -> &[&'r str] {
+        &self.allowed_methods
+    }
+
+    /// Returns whether a parameter or the wildcard captured a byte at or after `offset`.
+    fn captures_from(&self, offset: usize) -> bool {
+        self.params
+            .iter()
+            .chain(&self.wildcard)
+            .any(|&(_, end)| end > offset)
     }
 
     fn new_params<'a>(&'a self, names: &'a ParamNames) -> Params<'a> {
         Params {
             names: Cow::Borrowed(names),
-            path: Cow::Borrowed(&self.path),
-            spans: Cow::Borrowed(&self.params),
+            path: Cow::Borrowed(self.path),
+            spans: Cow::Borrowed(&self.params[..]),
             wildcard: self.wildcard,
         }
     }
+
+    fn new_scope_params<'a>(&'a self, scope: &'a Endpoint<T>) -> Option<Params<'a>> {
+        let names = scope.names.as_ref()?;
+        let len = names.names.len().min(self.params.len());
+        Some(Params {
+            spans: Cow::Borrowed(&self.params[..len]),
+            wildcard: self.wildcard.filter(|_| names.has_wildcard),
+            ..self.new_params(names)
+        })
+    }
 }
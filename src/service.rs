@@ -0,0 +1,257 @@
+//! A `tower::Service` that dispatches requests to the services registered in a `Router`,
+//! and a `tower::Layer` that puts a `Router` in front of another service.
+
+use crate::{
+    endpoint::EndpointId, //
+    param::Params,
+    router::Router,
+};
+use ::http::{header::ALLOW, HeaderValue, Request, Response, StatusCode};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A `Service` that routes each request to the service associated with the matched endpoint.
+///
+/// If a route matches the request, the request is dispatched to the service of
+/// that route. If the path matched a route that does not accept the request
+/// method, the request is dispatched to the "method not allowed" service.
+/// Otherwise, the fallback resolved by `Recognize::fallback` is used, and then the
+/// service of the innermost matching scope, and finally the "not found" service.
+/// When the "method not allowed" or "not found" service is not configured, an
+/// empty response with the status code `405` or `404` is returned. The `405`
+/// response carries an `Allow` header listing the methods of the routes that
+/// matched the path.
+///
+/// The matched `EndpointId` and the extracted `Params<'static>` are stored into
+/// the request extensions before dispatching, as in `Router::route_request`.
+pub struct RouterService<S> {
+    router: Arc<Router<S>>,
+    not_found: Option<S>,
+    method_not_allowed: Option<S>,
+}
+
+impl<S> RouterService<S> {
+    /// Creates a new `RouterService` from the specified router.
+    pub fn new(router: Router<S>) -> Self {
+        Self {
+            router: Arc::new(router),
+            not_found: None,
+            method_not_allowed: None,
+        }
+    }
+
+    /// Sets the service called when no route or scope matches the request.
+    pub fn not_found(self, service: S) -> Self {
+        Self {
+            not_found: Some(service),
+            ..self
+        }
+    }
+
+    /// Sets the service called when the matched route does not accept the request method.
+    pub fn method_not_allowed(self, service: S) -> Self {
+        Self {
+            method_not_allowed: Some(service),
+            ..self
+        }
+    }
+
+    /// Returns a reference to the underlying router.
+    pub fn router(&self) -> &Router<S> {
+        &self.router
+    }
+}
+
+impl<S: Clone> Clone for RouterService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+            not_found: self.not_found.clone(),
+            method_not_allowed: self.method_not_allowed.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for RouterService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouterService")
+            .field("not_found", &self.not_found.is_some())
+            .field("method_not_allowed", &self.method_not_allowed.is_some())
+            .finish()
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for RouterService<S>
+where
+    S: Service<Request<B>, Response = Response<RB>> + Clone,
+    RB: Default,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S, Request<B>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The readiness of each endpoint is checked in the returned future.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let (target, matched) = {
+            let recognize = self.router.recognize_request(&request);
+            if let Some((route, params)) = recognize.route() {
+                let matched = (route.id(), params.map(Params::into_owned));
+                (Ok(route.data().clone()), Some(matched))
+            } else if recognize.is_method_not_allowed() {
+                let target = self.method_not_allowed.clone().ok_or_else(|| {
+                    let mut response = empty_response(StatusCode::METHOD_NOT_ALLOWED);
+                    let allow = recognize.allowed_methods().join(", ");
+                    if let Ok(allow) = HeaderValue::from_str(&allow) {
+                        response.headers_mut().insert(ALLOW, allow);
+                    }
+                    response
+                });
+                (target, None)
+            } else if let Some(fallback) = recognize.fallback() {
+                let params = recognize
+                    .scope()
+                    .and_then(|(_, params)| params.map(Params::into_owned));
+                (Ok(fallback.data().clone()), Some((fallback.id(), params)))
+            } else if let Some((scope, params)) = recognize.scope() {
+                let matched = (scope.id(), params.map(Params::into_owned));
+                (Ok(scope.data().clone()), Some(matched))
+            } else {
+                let target = self
+                    .not_found
+                    .clone()
+                    .ok_or_else(|| empty_response(StatusCode::NOT_FOUND));
+                (target, None)
+            }
+        };
+
+        if let Some((id, params)) = matched {
+            request.extensions_mut().insert::<EndpointId>(id);
+            if let Some(params) = params {
+                request.extensions_mut().insert(params);
+            }
+        }
+
+        let state = match target {
+            Ok(service) => State::NotReady(service, Some(request)),
+            Err(response) => State::Done(Some(response)),
+        };
+        ResponseFuture { state }
+    }
+}
+
+fn empty_response<B: Default>(status: StatusCode) -> Response<B> {
+    let mut response = Response::new(B::default());
+    *response.status_mut() = status;
+    response
+}
+
+/// A `Layer` that routes the requests with a `Router` before the wrapped service.
+///
+/// The produced `RouterService` dispatches the requests that match no route
+/// or scope to the wrapped service, which acts as the "not found" service.
+pub struct RouterLayer<S> {
+    router: Arc<Router<S>>,
+}
+
+impl<S> RouterLayer<S> {
+    /// Creates a new `RouterLayer` from the specified router.
+    pub fn new(router: Router<S>) -> Self {
+        Self {
+            router: Arc::new(router),
+        }
+    }
+}
+
+impl<S> Clone for RouterLayer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for RouterLayer<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouterLayer").finish()
+    }
+}
+
+impl<S> Layer<S> for RouterLayer<S> {
+    type Service = RouterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RouterService {
+            router: self.router.clone(),
+            not_found: Some(inner),
+            method_not_allowed: None,
+        }
+    }
+}
+
+/// The future returned by `RouterService`.
+pub struct ResponseFuture<S, R>
+where
+    S: Service<R>,
+{
+    state: State<S, R>,
+}
+
+enum State<S, R>
+where
+    S: Service<R>,
+{
+    NotReady(S, Option<R>),
+    Called(Pin<Box<S::Future>>),
+    Done(Option<S::Response>),
+}
+
+// The inner future is boxed, and the service is never pinned.
+impl<S, R> Unpin for ResponseFuture<S, R> where S: Service<R> {}
+
+impl<S, R> fmt::Debug for ResponseFuture<S, R>
+where
+    S: Service<R>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish()
+    }
+}
+
+impl<S, R> Future for ResponseFuture<S, R>
+where
+    S: Service<R>,
+{
+    type Output = Result<S::Response, S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            self.state = match self.state {
+                State::NotReady(ref mut service, ref mut request) => {
+                    match service.poll_ready(cx) {
+                        Poll::Ready(Ok(())) => (),
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                    let request = request.take().expect("the request has already been sent");
+                    State::Called(Box::pin(service.call(request)))
+                }
+                State::Called(ref mut future) => return future.as_mut().poll(cx),
+                State::Done(ref mut response) => {
+                    let response = response.take().expect("the future has already completed");
+                    return Poll::Ready(Ok(response));
+                }
+            };
+        }
+    }
+}
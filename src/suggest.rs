@@ -0,0 +1,195 @@
+use crate::{
+    endpoint::{Endpoint, EndpointKind},
+    query,
+    router::Router,
+    tree::parse_parameter,
+};
+use std::cmp;
+
+impl<T> Router<T> {
+    /// Returns at most `n` route patterns that are the most similar to the provided path.
+    ///
+    /// The routes are ranked by the edit distance between the segments of the path
+    /// and those of the route pattern. Parameters match any segment and wildcards
+    /// match any number of segments. Ties are broken in favor of the routes below
+    /// the deepest node reached while recognizing the path, and then by the
+    /// pattern itself.
+    ///
+    /// This is intended for building "did you mean" hints when `recognize` fails.
+    pub fn suggest(&self, path: &str, n: usize) -> Vec<&Endpoint<T>> {
+        let (path, _) = query::split_path(path);
+        let near = self.tree.routes_near(path.as_ref());
+        let segments: Vec<&str> = split_segments(path).collect();
+
+        let mut candidates: Vec<_> = self
+            .endpoints()
+            .filter(|endpoint| endpoint.kind() == EndpointKind::Route)
+            .map(|endpoint| {
+                let pattern = parse_pattern(endpoint.path());
+                let distance = distance(&pattern, &segments);
+                let is_near = near.contains(&endpoint.id());
+                (distance, !is_near, endpoint)
+            })
+            .collect();
+        candidates.sort_by(|a, b| (a.0, a.1, a.2.path()).cmp(&(b.0, b.1, b.2.path())));
+
+        candidates
+            .into_iter()
+            .take(n)
+            .map(|(_, _, endpoint)| endpoint)
+            .collect()
+    }
+}
+
+fn split_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
+
+/// Splits a route pattern into segments.
+///
+/// A constrained parameter may span several segments, so it is treated as a wildcard.
+fn parse_pattern(pattern: &str) -> Vec<PatternSegment<'_>> {
+    let mut segments = vec![];
+    let mut rest = pattern;
+    loop {
+        rest = rest.trim_start_matches('/');
+        if rest.is_empty() {
+            return segments;
+        }
+        let constrained = if rest.starts_with(':') {
+            parse_parameter(rest.as_bytes())
+                .ok()
+                .filter(|param| param.constraint.is_some())
+        } else {
+            None
+        };
+        let end = match constrained {
+            Some(param) => {
+                segments.push(PatternSegment::Wildcard(""));
+                param.len
+            }
+            None => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                segments.push(PatternSegment::parse(&rest[..end]));
+                end
+            }
+        };
+        rest = &rest[end..];
+    }
+}
+
+#[derive(Debug)]
+enum PatternSegment<'a> {
+    Static(&'a str),
+    Param,
+    Wildcard(&'a str),
+}
+
+impl<'a> PatternSegment<'a> {
+    fn parse(segment: &'a str) -> Self {
+        if segment.starts_with(':') {
+            PatternSegment::Param
+        } else if let Some(suffix) = segment.strip_prefix('*') {
+            PatternSegment::Wildcard(suffix)
+        } else {
+            PatternSegment::Static(segment)
+        }
+    }
+
+    fn weight(&self) -> usize {
+        match *self {
+            PatternSegment::Static(s) => s.len(),
+            PatternSegment::Param => 1,
+            PatternSegment::Wildcard(suffix) => suffix.len(),
+        }
+    }
+
+    fn cost(&self, segment: &str) -> usize {
+        match *self {
+            PatternSegment::Static(s) => levenshtein(s.as_bytes(), segment.as_bytes()),
+            PatternSegment::Param => 0,
+            PatternSegment::Wildcard(suffix) => {
+                if segment.ends_with(suffix) {
+                    0
+                } else {
+                    suffix.len()
+                }
+            }
+        }
+    }
+}
+
+/// Computes the segment-wise edit distance between a route pattern and a path.
+fn distance(pattern: &[PatternSegment<'_>], segments: &[&str]) -> usize {
+    let mut dp = vec![vec![0; segments.len() + 1]; pattern.len() + 1];
+    for j in 1..=segments.len() {
+        dp[0][j] = dp[0][j - 1] + segments[j - 1].len();
+    }
+    for i in 1..=pattern.len() {
+        dp[i][0] = dp[i - 1][0] + pattern[i - 1].weight();
+        let mut start = dp[i - 1][0];
+        for j in 1..=segments.len() {
+            // a wildcard may consume any number of segments before the current one.
+            let prev = match pattern[i - 1] {
+                PatternSegment::Wildcard(..) => start,
+                _ => dp[i - 1][j - 1],
+            };
+            dp[i][j] = cmp::min(
+                prev + pattern[i - 1].cost(segments[j - 1]),
+                cmp::min(
+                    dp[i - 1][j] + pattern[i - 1].weight(),
+                    dp[i][j - 1] + segments[j - 1].len(),
+                ),
+            );
+            start = cmp::min(start, dp[i - 1][j]);
+        }
+    }
+    dp[pattern.len()][segments.len()]
+}
+
+fn levenshtein(s1: &[u8], s2: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..=s2.len()).collect();
+    for (i, &c1) in s1.iter().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &c2) in s2.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if c1 == c2 {
+                prev
+            } else {
+                1 + cmp::min(prev, cmp::min(row[j], current))
+            };
+            prev = current;
+        }
+    }
+    row[s2.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein(b"users", b"users"), 0);
+        assert_eq!(levenshtein(b"users", b"usres"), 2);
+        assert_eq!(levenshtein(b"users", b"user"), 1);
+        assert_eq!(levenshtein(b"", b"abc"), 3);
+    }
+
+    #[test]
+    fn segment_distance() {
+        let pattern = parse_pattern("/users/:id/books");
+        assert_eq!(distance(&pattern, &["users", "42", "books"]), 0);
+        assert_eq!(distance(&pattern, &["users", "42", "book"]), 1);
+        assert_eq!(distance(&pattern, &["users", "42"]), 5);
+
+        let pattern = parse_pattern("/static/*.html");
+        assert_eq!(distance(&pattern, &["static", "a", "b", "index.html"]), 0);
+        assert_eq!(distance(&pattern, &["static", "index.htm"]), 5);
+
+        let pattern = parse_pattern("/archive/:date<\\d+/\\d+>/index");
+        assert_eq!(distance(&pattern, &["archive", "2019", "05", "index"]), 0);
+        assert_eq!(distance(&pattern, &["archive", "2019", "05"]), 5);
+    }
+}
@@ -0,0 +1,252 @@
+//! Loading and dumping route tables.
+//!
+//! A route table is a line-oriented text file. Each non-empty line that does
+//! not start with `#` declares a single endpoint:
+//!
+//! ```text
+//! # kind  path              handler        options
+//! route   /users/:id        users::show    name=user methods=GET,HEAD
+//! route   /users/:id/books  users::books
+//! route   /posts/:id        posts::show_v1 versions=1..3
+//! route   /posts/:id        posts::show    versions=3..
+//! scope   /api/             api            name=api
+//! fallback /api/            api::not_found
+//! fallback *                not_found
+//! ```
+//!
+//! The columns are separated by whitespace:
+//!
+//! * `kind` - one of `route`, `scope` or `fallback`.
+//! * `path` - the path pattern, in the same syntax as `Router::add_route`.
+//!   For a fallback, the path of a scope declared earlier in the table
+//!   (see `Router::set_scope_fallback`), or `*` for the fallback of the router
+//!   (see `Router::set_fallback`). Since the latter is ambiguous with a scope
+//!   at `*`, a line `fallback *` is rejected once such a scope is declared.
+//! * `handler` - an opaque key that is stored as the data of the endpoint.
+//! * `options` - zero or more `key=value` pairs. The supported keys are
+//!   `name` (see `Router::set_name`), `methods`, a comma-separated list of
+//!   HTTP methods (see `Router::set_methods`), `host` (see `Router::set_host`),
+//!   and `versions`, a range of API versions such as `1..3`, `1..=2` or `3..`
+//!   (routes only, see `Router::add_versioned`). Each key may appear at most
+//!   once per line, and its value must not be empty.
+
+use crate::{
+    endpoint::EndpointKind, //
+    error::Result,
+    router::Router,
+};
+use std::{
+    io::{self, BufRead, Write},
+    ops::{Bound, RangeInclusive},
+};
+
+impl Router<String> {
+    /// Creates a router from a route table.
+    ///
+    /// The data associated with each endpoint is its handler key.
+    /// See the [module documentation](table/index.html) for the format.
+    pub fn from_table<R: BufRead>(reader: R) -> Result<Self> {
+        let mut router = Router::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            parse_line(&mut router, &line).map_err(|err| err.at_line(i + 1))?;
+        }
+        Ok(router)
+    }
+}
+
+fn parse_line(router: &mut Router<String>, line: &str) -> Result<()> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(());
+    }
+
+    let mut columns = line.split_whitespace();
+    let kind = match columns.next() {
+        Some("route") => EndpointKind::Route,
+        Some("scope") => EndpointKind::Scope,
+        Some("fallback") => EndpointKind::Fallback,
+        Some(kind) => {
+            bail!(format!("unknown endpoint kind `{}`", kind));
+        }
+        None => unreachable!(),
+    };
+    let path = columns.next().ok_or("missing path")?;
+    let handler = columns.next().ok_or("missing handler key")?;
+
+    let mut options = vec![];
+    for option in columns {
+        let (key, value) = match option.find('=') {
+            Some(pos) => (&option[..pos], &option[pos + 1..]),
+            None => {
+                bail!(format!("invalid option `{}`", option));
+            }
+        };
+        if !["name", "methods", "host", "versions"].contains(&key) {
+            bail!(format!("invalid option `{}`", option));
+        }
+        // `methods=,` would otherwise mean that any method is accepted.
+        if value.is_empty() || (key == "methods" && value.split(',').all(str::is_empty)) {
+            bail!(format!("the option `{}` must not be empty", key));
+        }
+        if options.iter().any(|&(k, _)| k == key) {
+            bail!(format!("the option `{}` is specified more than once", key));
+        }
+        options.push((key, value));
+    }
+
+    let versions = match options.iter().find(|&&(key, _)| key == "versions") {
+        Some(..) if kind != EndpointKind::Route => {
+            bail!("only routes can be versioned");
+        }
+        Some(&(_, versions)) => Some(parse_versions(versions)?),
+        None => None,
+    };
+
+    let id = match kind {
+        EndpointKind::Route => match versions {
+            Some(versions) => router.add_versioned(path, versions, handler.to_owned())?,
+            None => router.add_route(path, handler.to_owned())?,
+        },
+        EndpointKind::Scope => router.add_scope(path, handler.to_owned())?,
+        EndpointKind::Fallback => {
+            let scope = router
+                .endpoints()
+                .find(|e| e.kind() == EndpointKind::Scope && e.path() == path)
+                .map(|e| e.id());
+            match scope {
+                Some(..) if path == "*" => {
+                    bail!("`fallback *` is ambiguous with the scope `*`");
+                }
+                Some(scope) => router.set_scope_fallback(scope, handler.to_owned())?,
+                None if path == "*" => router.set_fallback(handler.to_owned())?,
+                None => {
+                    bail!(format!("no scope with the path `{}`", path));
+                }
+            }
+        }
+    };
+
+    for (key, value) in options {
+        match key {
+            "name" => router.set_name(id, value)?,
+            "methods" => router.set_methods(id, value.split(',').filter(|m| !m.is_empty()))?,
+            "host" => router.set_host(id, value)?,
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_versions(s: &str) -> Result<(Bound<u32>, Bound<u32>)> {
+    let invalid = || format!("invalid range of versions `{}`", s);
+    let bound = |n: &str, f: fn(u32) -> Bound<u32>| -> Result<Bound<u32>> {
+        if n.is_empty() {
+            Ok(Bound::Unbounded)
+        } else {
+            n.parse().map(f).map_err(|_| invalid().into())
+        }
+    };
+
+    let (start, end) = match s.find("..") {
+        Some(pos) => {
+            let start = bound(&s[..pos], Bound::Included)?;
+            let end = match s[pos + 2..].strip_prefix('=') {
+                Some(end) if !end.is_empty() => bound(end, Bound::Included)?,
+                Some(..) => {
+                    bail!(invalid());
+                }
+                None => bound(&s[pos + 2..], Bound::Excluded)?,
+            };
+            (start, end)
+        }
+        None if !s.is_empty() => (bound(s, Bound::Included)?, bound(s, Bound::Included)?),
+        None => {
+            bail!(invalid());
+        }
+    };
+    Ok((start, end))
+}
+
+fn format_versions(versions: &RangeInclusive<u32>) -> String {
+    if *versions.end() == u32::MAX {
+        format!("{}..", versions.start())
+    } else {
+        format!("{}..={}", versions.start(), versions.end())
+    }
+}
+
+impl<T> Router<T>
+where
+    T: AsRef<str>,
+{
+    /// Writes the endpoints in this router as a route table.
+    ///
+    /// The output can be read back using `Router::from_table`. An error is
+    /// returned if a column would be empty or contain whitespace, or if a
+    /// fallback at `*` follows a scope at `*`, since it could not be read back
+    /// as the same value.
+    pub fn write_table<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut wildcard_scope = false;
+        for endpoint in self.endpoints() {
+            let handler = endpoint.data().as_ref();
+            check_column("path", endpoint.path(), &[])?;
+            check_column("handler key", handler, &[])?;
+            if let Some(name) = endpoint.name() {
+                check_column("name", name, &[])?;
+            }
+            for method in endpoint.methods() {
+                check_column("method", method, &[','])?;
+            }
+            if let Some(host) = endpoint.host() {
+                check_column("host", host, &[])?;
+            }
+
+            if endpoint.path() == "*" {
+                match endpoint.kind() {
+                    EndpointKind::Scope => wildcard_scope = true,
+                    EndpointKind::Fallback if wildcard_scope => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "a fallback at `*` after the scope `*` cannot be written",
+                        ));
+                    }
+                    _ => (),
+                }
+            }
+
+            let kind = match endpoint.kind() {
+                EndpointKind::Route => "route",
+                EndpointKind::Scope => "scope",
+                EndpointKind::Fallback => "fallback",
+            };
+            write!(writer, "{} {} {}", kind, endpoint.path(), handler)?;
+            if let Some(name) = endpoint.name() {
+                write!(writer, " name={}", name)?;
+            }
+            if !endpoint.methods().is_empty() {
+                write!(writer, " methods={}", endpoint.methods().join(","))?;
+            }
+            if let Some(host) = endpoint.host() {
+                write!(writer, " host={}", host)?;
+            }
+            if let Some(versions) = endpoint.versions() {
+                write!(writer, " versions={}", format_versions(versions))?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns an error if the value cannot be written as a column of a route table.
+fn check_column(what: &str, value: &str, delimiters: &[char]) -> io::Result<()> {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || delimiters.contains(&c)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the {} {:?} cannot be written", what, value),
+        ));
+    }
+    Ok(())
+}
@@ -1,36 +1,167 @@
+mod conflict;
+mod dot;
 mod insert;
+mod print;
 mod recognize;
 
-use crate::endpoint::EndpointId;
+pub(crate) use self::{conflict::example_path, insert::parse_parameter};
+use crate::{endpoint::EndpointId, matcher::SegmentMatcher};
+use regex::bytes::Regex;
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
 
-#[derive(Debug, Default)]
+/// The routing tree.
+///
+/// The nodes are shared through `Arc`s, so cloning a tree is cheap, and
+/// inserting a path into a clone only copies the nodes along that path.
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Tree {
-    root: Node,
+    root: Arc<Node>,
+    /// The segment matchers that constraints may refer to by name.
+    pub(crate) matchers: HashMap<String, Arc<dyn SegmentMatcher>>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 pub(crate) struct Node {
     static_segments: Vec<StaticSegment>,
-    param_segment: Option<Box<Node>>,
+    constrained_segments: Vec<ConstrainedSegment>,
+    param_segment: Option<Arc<Node>>,
     wildcard_segments: Vec<WildcardSegment>,
 
-    pub(crate) route: Option<EndpointId>,
+    /// The routes terminated at this node, in the order they are tried.
+    pub(crate) routes: Vec<EndpointId>,
     pub(crate) scope: Option<EndpointId>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 struct StaticSegment {
     segment: Vec<u8>,
-    child: Node,
+    child: Arc<Node>,
+}
+
+/// A parameter with a constraint, e.g. `:date<\d{4}/\d{2}/\d{2}>` or `:code<sku>`.
+#[derive(Debug, Clone)]
+struct ConstrainedSegment {
+    pattern: String,
+    constraint: Constraint,
+    child: Arc<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Constraint {
+    /// A regular expression, whose match may span several segments.
+    Regex(Regex),
+    /// A registered segment matcher, which only matches a single segment.
+    Matcher(Arc<dyn SegmentMatcher>),
+}
+
+#[cfg(test)]
+impl PartialEq for ConstrainedSegment {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.child == other.child
+    }
+}
+
+impl ConstrainedSegment {
+    /// Returns the possible end positions of a value starting at `offset`,
+    /// the longest one first.
+    fn ends(&self, path: &[u8], offset: usize) -> Vec<usize> {
+        match self.constraint {
+            Constraint::Regex(ref regex) => regex_ends(regex, path, offset),
+            Constraint::Matcher(ref matcher) => {
+                let end = path[offset..]
+                    .iter()
+                    .position(|&c| c == b'/')
+                    .map_or(path.len(), |pos| offset + pos);
+                match std::str::from_utf8(&path[offset..end]) {
+                    Ok(seg) if matcher.matches(seg) => vec![end],
+                    _ => vec![],
+                }
+            }
+        }
+    }
+}
+
+/// Compiles the constraint of a parameter, which must match the whole value.
+fn compile_constraint(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})\\z", pattern))
+}
+
+/// Returns the possible end positions of a value starting at `offset` and
+/// matching `regex`, the longest one first.
+fn regex_ends(regex: &Regex, path: &[u8], offset: usize) -> Vec<usize> {
+    (offset..=path.len())
+        .rev()
+        .filter(|&end| end == path.len() || path[end] == b'/')
+        .filter(|&end| regex.is_match(&path[offset..end]))
+        .collect()
 }
 
+/// A parameter constraint used by the code generated by `routes!`.
+///
+/// The pattern has already been validated when the macro was expanded, so
+/// the regular expression is compiled on the first use.
 #[derive(Debug)]
+pub struct RegexConstraint {
+    pattern: &'static str,
+    regex: OnceLock<Regex>,
+}
+
+impl RegexConstraint {
+    /// Creates a constraint from the pattern written in the route.
+    pub const fn new(pattern: &'static str) -> Self {
+        Self {
+            pattern,
+            regex: OnceLock::new(),
+        }
+    }
+
+    /// Returns the possible end positions of a value starting at `offset`,
+    /// the longest one first.
+    pub fn ends(&self, path: &[u8], offset: usize) -> Vec<usize> {
+        let regex = self.regex.get_or_init(|| {
+            compile_constraint(self.pattern).expect("the constraint is validated by `routes!`")
+        });
+        regex_ends(regex, path, offset)
+    }
+}
+
+#[derive(Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 struct WildcardSegment {
     slug: Vec<u8>,
-    child: Node,
+    child: Arc<Node>,
+}
+
+impl Tree {
+    /// Returns whether a parameter in the tree is constrained by the regular expression `pattern`.
+    pub(crate) fn has_regex_constraint(&self, pattern: &str) -> bool {
+        self.root.has_regex_constraint(pattern)
+    }
+}
+
+impl Node {
+    fn has_regex_constraint(&self, pattern: &str) -> bool {
+        self.constrained_segments.iter().any(|s| {
+            (s.pattern == pattern && matches!(s.constraint, Constraint::Regex(..)))
+                || s.child.has_regex_constraint(pattern)
+        }) || self
+            .static_segments
+            .iter()
+            .any(|s| s.child.has_regex_constraint(pattern))
+            || self
+                .param_segment
+                .as_ref()
+                .is_some_and(|child| child.has_regex_constraint(pattern))
+            || self
+                .wildcard_segments
+                .iter()
+                .any(|s| s.child.has_regex_constraint(pattern))
+    }
 }
 
 impl StaticSegment {
@@ -38,13 +169,13 @@ impl StaticSegment {
         let (seg1, seg2) = self.segment.split_at(i);
         *self = Self {
             segment: seg1.to_owned(),
-            child: Node {
+            child: Arc::new(Node {
                 static_segments: vec![Self {
                     segment: seg2.to_owned(),
-                    child: std::mem::replace(&mut self.child, Node::default()),
+                    child: std::mem::take(&mut self.child),
                 }],
                 ..Default::default()
-            },
+            }),
         };
     }
 }
@@ -0,0 +1,319 @@
+use super::{ConstrainedSegment, Constraint, Node, StaticSegment, Tree, WildcardSegment};
+use crate::endpoint::EndpointId;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// An element of a route pattern, as seen by the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'t> {
+    /// A literal byte.
+    Byte(u8),
+    /// A parameter, matching one or more bytes other than `/`.
+    Param,
+    /// A parameter constrained by the specified regular expression.
+    ///
+    /// The constraint is not interpreted, and the parameter is treated as a
+    /// wildcard when building examples.
+    Constrained(&'t str),
+    /// A parameter checked by the specified segment matcher.
+    ///
+    /// The matcher is not consulted, and the parameter is treated as a plain
+    /// parameter when building examples.
+    Matcher(&'t str),
+    /// A wildcard, matching one or more arbitrary bytes.
+    Wildcard,
+}
+
+impl Tree {
+    /// Returns the patterns of all routes in the tree, in the order they are visited.
+    pub(crate) fn route_patterns(&self) -> Vec<(EndpointId, Vec<Token<'_>>)> {
+        let mut patterns = vec![];
+        self.root.collect_patterns(&mut vec![], &mut patterns);
+        patterns
+    }
+}
+
+impl Node {
+    fn collect_patterns<'t>(
+        &'t self,
+        prefix: &mut Vec<Token<'t>>,
+        patterns: &mut Vec<(EndpointId, Vec<Token<'t>>)>,
+    ) {
+        for &route in &self.routes {
+            patterns.push((route, prefix.clone()));
+        }
+
+        let len = prefix.len();
+        for StaticSegment { segment, child } in &self.static_segments {
+            prefix.extend(segment.iter().map(|&b| Token::Byte(b)));
+            child.collect_patterns(prefix, patterns);
+            prefix.truncate(len);
+        }
+        for ConstrainedSegment {
+            pattern,
+            constraint,
+            child,
+        } in &self.constrained_segments
+        {
+            prefix.push(match constraint {
+                Constraint::Regex(..) => Token::Constrained(pattern),
+                Constraint::Matcher(..) => Token::Matcher(pattern),
+            });
+            child.collect_patterns(prefix, patterns);
+            prefix.truncate(len);
+        }
+        if let Some(child) = &self.param_segment {
+            prefix.push(Token::Param);
+            child.collect_patterns(prefix, patterns);
+            prefix.truncate(len);
+        }
+        for WildcardSegment { slug, child } in &self.wildcard_segments {
+            prefix.push(Token::Wildcard);
+            prefix.extend(slug.iter().map(|&b| Token::Byte(b)));
+            child.collect_patterns(prefix, patterns);
+            prefix.truncate(len);
+        }
+    }
+}
+
+/// A set of positions in a pattern.
+///
+/// A position `(i, true)` means that the variable at `i` has consumed at least
+/// one byte and may either continue or be left.
+type States = BTreeSet<(usize, bool)>;
+
+/// The states of the two patterns being intersected.
+type Pair = (States, States);
+
+fn closure(pattern: &[Token<'_>], mut states: States) -> States {
+    let extra: Vec<_> = states
+        .iter()
+        .filter(|&&(i, started)| started && i < pattern.len())
+        .map(|&(i, _)| (i + 1, false))
+        .collect();
+    states.extend(extra);
+    states
+}
+
+fn step(pattern: &[Token<'_>], states: &States, c: u8) -> States {
+    let mut next = States::new();
+    for &(i, started) in states {
+        match pattern.get(i) {
+            Some(&Token::Byte(b)) if !started && b == c => {
+                next.insert((i + 1, false));
+            }
+            Some(Token::Param) | Some(Token::Matcher(..)) if c != b'/' => {
+                next.insert((i, true));
+            }
+            Some(Token::Wildcard) | Some(Token::Constrained(..)) => {
+                next.insert((i, true));
+            }
+            _ => (),
+        }
+    }
+    closure(pattern, next)
+}
+
+/// The maximum number of candidate paths tried by `example_path` after the
+/// shortest one was rejected.
+const MAX_CANDIDATES: usize = 4096;
+
+/// Returns the shortest path matched by both patterns and accepted by `accept`, if any.
+///
+/// Since the constrained parameters are approximated, the shortest common path
+/// may be rejected by their constraints. In that case, the other common paths
+/// are tried in order of length, up to `MAX_CANDIDATES` of them.
+pub(crate) fn example_path(
+    a: &[Token<'_>],
+    b: &[Token<'_>],
+    accept: &mut dyn FnMut(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+    // The bytes used for the values of the parameters and wildcards come first,
+    // so that they are preferred when building the example.
+    let mut alphabet = vec![b'x', b'0'];
+    let mut literals: Vec<u8> = a
+        .iter()
+        .chain(b)
+        .filter_map(|t| match *t {
+            Token::Byte(b) => Some(b),
+            _ => None,
+        })
+        .filter(|b| !alphabet.contains(b))
+        .collect();
+    literals.sort_unstable();
+    literals.dedup();
+    alphabet.extend(literals);
+
+    let start = (
+        closure(a, Some((0, false)).into_iter().collect()),
+        closure(b, Some((0, false)).into_iter().collect()),
+    );
+    let is_accepted =
+        |(sa, sb): &Pair| sa.contains(&(a.len(), false)) && sb.contains(&(b.len(), false));
+    let next_pairs = |current: &Pair| -> Vec<(Pair, u8)> {
+        alphabet
+            .iter()
+            .map(|&c| ((step(a, &current.0, c), step(b, &current.1, c)), c))
+            .filter(|(next, _)| !next.0.is_empty() && !next.1.is_empty())
+            .collect()
+    };
+
+    // Search for the shortest common path, visiting each pair of states once.
+    let mut parents: HashMap<Pair, Option<(Pair, u8)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    parents.insert(start.clone(), None);
+    queue.push_back(start.clone());
+
+    let mut shortest = None;
+    while let Some(current) = queue.pop_front() {
+        if is_accepted(&current) {
+            let mut path = vec![];
+            let mut state = &current;
+            while let Some((parent, c)) = &parents[state] {
+                path.push(*c);
+                state = parent;
+            }
+            path.reverse();
+            shortest = Some(path);
+            break;
+        }
+
+        for (next, c) in next_pairs(&current) {
+            if !parents.contains_key(&next) {
+                parents.insert(next.clone(), Some((current.clone(), c)));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let shortest = shortest?;
+    if accept(&shortest) {
+        return Some(shortest);
+    }
+
+    // Enumerate the other common paths, without merging the equal pairs of states.
+    let mut queue = VecDeque::new();
+    queue.push_back((start, vec![]));
+    let mut budget = MAX_CANDIDATES;
+    while let Some((current, path)) = queue.pop_front() {
+        if is_accepted(&current) && path != shortest && accept(&path) {
+            return Some(path);
+        }
+        for (next, c) in next_pairs(&current) {
+            if budget == 0 {
+                return None;
+            }
+            budget -= 1;
+            let mut path = path.clone();
+            path.push(c);
+            queue.push_back((next, path));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(path: &str) -> Tree {
+        let mut tree = Tree::default();
+        tree.insert(path.as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree
+    }
+
+    fn example(a: &str, b: &str) -> Option<String> {
+        let (a, b) = (tree(a), tree(b));
+        example_path(
+            &a.route_patterns()[0].1,
+            &b.route_patterns()[0].1,
+            &mut |_| true,
+        )
+        .map(|path| String::from_utf8(path).unwrap())
+    }
+
+    #[test]
+    fn route_patterns() {
+        let mut tree = Tree::default();
+        tree.insert(b"/a/:x", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/a/*.b", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        assert_eq!(
+            tree.route_patterns(),
+            vec![
+                (
+                    EndpointId(0),
+                    vec![
+                        Token::Byte(b'/'),
+                        Token::Byte(b'a'),
+                        Token::Byte(b'/'),
+                        Token::Param
+                    ]
+                ),
+                (
+                    EndpointId(1),
+                    vec![
+                        Token::Byte(b'/'),
+                        Token::Byte(b'a'),
+                        Token::Byte(b'/'),
+                        Token::Wildcard,
+                        Token::Byte(b'.'),
+                        Token::Byte(b'b'),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(example("/a/:x", "/a/*"), Some("/a/x".into()));
+        assert_eq!(example("/a/:x", "/a/b"), Some("/a/b".into()));
+        assert_eq!(
+            example("/files/*.html", "/files/*/index.html"),
+            Some("/files/x/index.html".into())
+        );
+        assert_eq!(example("/:x/b", "/a/:y"), Some("/a/b".into()));
+        assert_eq!(example("/*", "/a/:y/c"), Some("/a/x/c".into()));
+
+        assert_eq!(
+            example("/a/:x<\\d+/\\d+>", "/a/:y/:z"),
+            Some("/a/x/x".into())
+        );
+
+        assert_eq!(example("/a/:x", "/a/:x/b"), None);
+        assert_eq!(example("/a/:x", "/b/*"), None);
+        assert_eq!(example("/a/*.html", "/a/*.css"), None);
+    }
+
+    #[test]
+    fn examples_with_constraints() {
+        let (a, b) = (tree("/a/:x<\\d+>"), tree("/a/:y"));
+        let is_digits = |path: &[u8]| path[3..].iter().all(u8::is_ascii_digit);
+        assert_eq!(
+            example_path(
+                &a.route_patterns()[0].1,
+                &b.route_patterns()[0].1,
+                &mut |path| is_digits(path)
+            ),
+            Some(b"/a/0".to_vec())
+        );
+        assert_eq!(
+            example_path(
+                &a.route_patterns()[0].1,
+                &b.route_patterns()[0].1,
+                &mut |_| false
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use super::{ConstrainedSegment, Node, StaticSegment, Tree, WildcardSegment};
+use crate::endpoint::EndpointId;
+use std::fmt::{self, Write};
+
+impl Tree {
+    /// Writes this tree as a Graphviz DOT graph, using `path` to look up the
+    /// original path of each endpoint.
+    pub(crate) fn write_dot<'p>(
+        &self,
+        out: &mut dyn Write,
+        path: &dyn Fn(EndpointId) -> Option<&'p str>,
+    ) -> fmt::Result {
+        let mut cx = DotContext {
+            out,
+            path,
+            next_id: 0,
+        };
+        writeln!(cx.out, "digraph router {{")?;
+        writeln!(cx.out, "    node [shape=box, fontname=monospace];")?;
+        writeln!(cx.out, "    edge [fontname=monospace];")?;
+        cx.write_node(&self.root)?;
+        writeln!(cx.out, "}}")
+    }
+}
+
+struct DotContext<'a, 'p> {
+    out: &'a mut dyn Write,
+    path: &'a dyn Fn(EndpointId) -> Option<&'p str>,
+    next_id: usize,
+}
+
+impl<'a, 'p> DotContext<'a, 'p> {
+    fn write_node(&mut self, node: &Node) -> fmt::Result {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut label = String::new();
+        let routes = node.routes.iter().map(|&id| ("route", id));
+        for (kind, endpoint) in routes.chain(node.scope.map(|id| ("scope", id))) {
+            if !label.is_empty() {
+                label.push('\n');
+            }
+            label += &format!("{} #{}", kind, endpoint.0);
+            if let Some(path) = (self.path)(endpoint) {
+                label += &format!(" {}", path);
+            }
+        }
+        if label.is_empty() {
+            writeln!(self.out, "    n{} [label=\"\", shape=point];", id)?;
+        } else {
+            writeln!(
+                self.out,
+                "    n{} [label={}, style=bold];",
+                id,
+                quote(&label)
+            )?;
+        }
+
+        for StaticSegment { segment, child } in &node.static_segments {
+            let label = String::from_utf8_lossy(segment);
+            self.write_edge(id, &label, "solid", child)?;
+        }
+        for ConstrainedSegment { pattern, child, .. } in &node.constrained_segments {
+            self.write_edge(id, &format!(":<{}>", pattern), "dashed", child)?;
+        }
+        if let Some(child) = &node.param_segment {
+            self.write_edge(id, ":", "dashed", child)?;
+        }
+        for WildcardSegment { slug, child } in &node.wildcard_segments {
+            let label = format!("*{}", String::from_utf8_lossy(slug));
+            self.write_edge(id, &label, "dotted", child)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_edge(&mut self, from: usize, label: &str, style: &str, child: &Node) -> fmt::Result {
+        let to = self.next_id;
+        writeln!(
+            self.out,
+            "    n{} -> n{} [label={}, style={}];",
+            from,
+            to,
+            quote(label),
+            style
+        )?;
+        self.write_node(child)
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot() {
+        let paths = ["/posts/:post", "/static/*.html", "/posts/"];
+        let mut tree = Tree::default();
+        tree.insert(paths[0].as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(paths[1].as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(paths[2].as_bytes(), &mut None).unwrap().scope = Some(EndpointId(2));
+
+        let mut out = String::new();
+        tree.write_dot(&mut out, &|id| paths.get(id.0).cloned())
+            .unwrap();
+
+        assert_eq!(
+            out,
+            r#"digraph router {
+    node [shape=box, fontname=monospace];
+    edge [fontname=monospace];
+    n0 [label="", shape=point];
+    n0 -> n1 [label="/", style=solid];
+    n1 [label="", shape=point];
+    n1 -> n2 [label="posts/", style=solid];
+    n2 [label="scope #2 /posts/", style=bold];
+    n2 -> n3 [label=":", style=dashed];
+    n3 [label="route #0 /posts/:post", style=bold];
+    n1 -> n4 [label="static/", style=solid];
+    n4 [label="", shape=point];
+    n4 -> n5 [label="*.html", style=dotted];
+    n5 [label="route #1 /static/*.html", style=bold];
+}
+"#
+        );
+    }
+}
@@ -1,5 +1,8 @@
-use super::{Node, StaticSegment, Tree, WildcardSegment};
-use crate::{error::Result, param::ParamNames};
+use super::{
+    compile_constraint, ConstrainedSegment, Constraint, Node, StaticSegment, Tree, WildcardSegment,
+};
+use crate::{error::Result, matcher::SegmentMatcher, param::ParamNames};
+use std::{collections::HashMap, sync::Arc};
 
 impl Tree {
     pub(crate) fn insert(
@@ -10,38 +13,37 @@ impl Tree {
         let mut cx = InsertContext {
             path,
             names: &mut *names,
+            matchers: &self.matchers,
         };
-        cx.run(&mut self.root)
+        cx.run(Arc::make_mut(&mut self.root))
     }
 }
 
 struct InsertContext<'a> {
     path: &'a [u8],
     names: &'a mut Option<ParamNames>,
+    matchers: &'a HashMap<String, Arc<dyn SegmentMatcher>>,
 }
 
 impl<'a> InsertContext<'a> {
     fn run<'n>(&mut self, mut current: &'n mut Node) -> Result<&'n mut Node> {
         loop {
-            match self.path.get(0) {
+            match self.path.first() {
                 Some(b':') => {
-                    if current.param_segment.is_some() {
-                        self.extract_parameter_name()?;
-                        current = &mut *current.param_segment.as_mut().unwrap();
-                        continue;
-                    }
+                    current = self.insert_param_segment(current)?;
+                    continue;
                 }
                 Some(b'*') => return self.insert_wildcard_segment(current),
                 Some(_) => {
                     if let Some(pos) = self.find_static_segment(current)? {
-                        current = &mut { current }.static_segments[pos].child;
+                        current = Arc::make_mut(&mut { current }.static_segments[pos].child);
                         continue;
                     }
                 }
-                None => (),
+                _ => (),
             }
 
-            if self.path.len() > 0 {
+            if !self.path.is_empty() {
                 return self.insert_remaining_path(current);
             }
 
@@ -63,13 +65,12 @@ impl<'a> InsertContext<'a> {
         Ok(None)
     }
 
-    fn extract_parameter_name(&mut self) -> Result<()> {
-        let end = self
-            .path
-            .iter()
-            .position(|&c| c == b'/')
-            .unwrap_or_else(|| self.path.len());
-        let name = &self.path[1..end];
+    fn insert_param_segment<'n>(&mut self, node: &'n mut Node) -> Result<&'n mut Node> {
+        let Parameter {
+            name,
+            constraint,
+            len,
+        } = parse_parameter(self.path)?;
         if !name
             .iter()
             .all(|&c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
@@ -81,19 +82,54 @@ impl<'a> InsertContext<'a> {
             .get_or_insert_with(Default::default)
             .names
             .push(name.to_owned());
-        self.path = &self.path[end..];
-        Ok(())
+        self.path = &self.path[len..];
+
+        let pattern = match constraint {
+            Some(pattern) => match std::str::from_utf8(pattern) {
+                Ok(pattern) => pattern,
+                Err(..) => {
+                    bail!("the constraint of a parameter must be valid UTF-8");
+                }
+            },
+            None => {
+                return Ok(Arc::make_mut(
+                    node.param_segment.get_or_insert_with(Default::default),
+                ))
+            }
+        };
+
+        if let Some(pos) = node
+            .constrained_segments
+            .iter()
+            .position(|s| s.pattern == pattern)
+        {
+            return Ok(Arc::make_mut(&mut node.constrained_segments[pos].child));
+        }
+
+        let constraint = match self.matchers.get(pattern) {
+            Some(matcher) => Constraint::Matcher(matcher.clone()),
+            // The value must match the whole constraint, not only a prefix of it.
+            None => match compile_constraint(pattern) {
+                Ok(regex) => Constraint::Regex(regex),
+                Err(err) => {
+                    bail!(format!("invalid parameter constraint: {}", err));
+                }
+            },
+        };
+        node.constrained_segments.push(ConstrainedSegment {
+            pattern: pattern.to_owned(),
+            constraint,
+            child: Arc::default(),
+        });
+        Ok(Arc::make_mut(
+            &mut node.constrained_segments.last_mut().unwrap().child,
+        ))
     }
 
     fn insert_remaining_path<'n>(&mut self, mut node: &'n mut Node) -> Result<&'n mut Node> {
-        while let Some(c) = self.path.get(0) {
+        while let Some(c) = self.path.first() {
             match c {
-                b':' => {
-                    self.extract_parameter_name()?;
-                    node = node
-                        .param_segment
-                        .get_or_insert_with(|| Box::new(Node::default()));
-                }
+                b':' => node = self.insert_param_segment(node)?,
                 b'*' => return self.insert_wildcard_segment(node),
                 _ => {
                     let end =
@@ -107,12 +143,23 @@ impl<'a> InsertContext<'a> {
                             self.path.len()
                         };
 
-                    node.static_segments.push(StaticSegment {
-                        segment: self.path[..end].to_owned(),
-                        child: Node::default(),
-                    });
+                    // The static segments are kept sorted, so that the shape of the
+                    // tree does not depend on the order in which the routes are added.
+                    let segment = &self.path[..end];
+                    let pos = node
+                        .static_segments
+                        .iter()
+                        .position(|s| s.segment[..] > *segment)
+                        .unwrap_or(node.static_segments.len());
+                    node.static_segments.insert(
+                        pos,
+                        StaticSegment {
+                            segment: segment.to_owned(),
+                            child: Arc::default(),
+                        },
+                    );
                     self.path = &self.path[end..];
-                    node = &mut node.static_segments.iter_mut().last().unwrap().child;
+                    node = Arc::make_mut(&mut node.static_segments[pos].child);
                 }
             }
         }
@@ -133,16 +180,90 @@ impl<'a> InsertContext<'a> {
             .iter_mut()
             .position(|s| s.slug == slug)
         {
-            return Ok(&mut node.wildcard_segments[pos].child);
+            return Ok(Arc::make_mut(&mut node.wildcard_segments[pos].child));
         }
 
-        node.wildcard_segments.push(WildcardSegment {
-            slug: slug.to_owned(),
-            child: Node::default(),
+        // Wildcard segments are kept sorted by the length of their slugs, so
+        // that the most specific one is tried first regardless of the order
+        // in which the routes are registered.
+        let pos = node
+            .wildcard_segments
+            .iter()
+            .position(|s| s.slug.len() < slug.len())
+            .unwrap_or(node.wildcard_segments.len());
+        node.wildcard_segments.insert(
+            pos,
+            WildcardSegment {
+                slug: slug.to_owned(),
+                child: Arc::default(),
+            },
+        );
+
+        Ok(Arc::make_mut(&mut node.wildcard_segments[pos].child))
+    }
+}
+
+/// A parameter in a route pattern, of the form `:name` or `:name<regex>`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Parameter<'a> {
+    pub(crate) name: &'a [u8],
+    pub(crate) constraint: Option<&'a [u8]>,
+    /// The length of the parameter in the pattern.
+    pub(crate) len: usize,
+}
+
+/// Parses the parameter at the start of `path`.
+pub(crate) fn parse_parameter(path: &[u8]) -> Result<Parameter<'_>> {
+    debug_assert_eq!(path.first(), Some(&b':'));
+
+    let name_end = path
+        .iter()
+        .position(|&c| c == b'/' || c == b'<')
+        .unwrap_or(path.len());
+    if path.get(name_end) != Some(&b'<') {
+        return Ok(Parameter {
+            name: &path[1..name_end],
+            constraint: None,
+            len: name_end,
         });
+    }
 
-        Ok(&mut node.wildcard_segments.iter_mut().last().unwrap().child)
+    // The constraint ends at the matching '>', skipping escaped characters
+    // and nested pairs such as the names of capture groups.
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut end = None;
+    for (i, &c) in path.iter().enumerate().skip(name_end) {
+        match c {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'<' => depth += 1,
+            b'>' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+            }
+            _ => (),
+        }
     }
+    let end = end.ok_or("the constraint of a parameter must be closed with '>'")?;
+    if end == name_end + 1 {
+        bail!("the constraint of a parameter must not be empty");
+    }
+    match path.get(end + 1) {
+        None | Some(b'/') => (),
+        Some(..) => {
+            bail!("a parameter constraint must be followed by '/' or the end of the path");
+        }
+    }
+
+    Ok(Parameter {
+        name: &path[1..name_end],
+        constraint: Some(&path[name_end + 1..end]),
+        len: end + 1,
+    })
 }
 
 fn longest_common_prefix(s1: &[u8], s2: &[u8]) -> usize {
@@ -150,6 +271,7 @@ fn longest_common_prefix(s1: &[u8], s2: &[u8]) -> usize {
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_map_or)]
 mod tests {
     use super::*;
     use crate::endpoint::EndpointId;
@@ -157,17 +279,21 @@ mod tests {
     #[test]
     fn root() {
         let mut tree = Tree::default();
-        tree.insert(b"/", &mut None).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/".into(),
                     child: Node {
-                        route: Some(EndpointId(0)),
+                        routes: vec![EndpointId(0)],
                         ..Default::default()
                     }
+                    .into()
                 },],
                 ..Default::default()
             }
@@ -177,25 +303,33 @@ mod tests {
     #[test]
     fn inclusive() {
         let mut tree = Tree::default();
-        tree.insert(b"/foo", &mut None).unwrap().route = Some(EndpointId(0));
-        tree.insert(b"/foo/bar", &mut None).unwrap().route = Some(EndpointId(1));
+        tree.insert(b"/foo", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/foo/bar", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/foo".into(),
                     child: Node {
-                        route: Some(EndpointId(0)),
+                        routes: vec![EndpointId(0)],
                         static_segments: vec![StaticSegment {
                             segment: "/bar".into(),
                             child: Node {
-                                route: Some(EndpointId(1)),
+                                routes: vec![EndpointId(1)],
                                 ..Default::default()
-                            },
+                            }
+                            .into(),
                         }],
                         ..Default::default()
                     }
+                    .into()
                 }],
                 ..Default::default()
             }
@@ -205,11 +339,17 @@ mod tests {
     #[test]
     fn different_suffix() {
         let mut tree = Tree::default();
-        tree.insert(b"/foo/bar", &mut None).unwrap().route = Some(EndpointId(0));
-        tree.insert(b"/foo/zoo", &mut None).unwrap().route = Some(EndpointId(1));
+        tree.insert(b"/foo/bar", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/foo/zoo", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/foo/".into(),
@@ -218,20 +358,23 @@ mod tests {
                             StaticSegment {
                                 segment: "bar".into(),
                                 child: Node {
-                                    route: Some(EndpointId(0)),
+                                    routes: vec![EndpointId(0)],
                                     ..Default::default()
-                                },
+                                }
+                                .into(),
                             },
                             StaticSegment {
                                 segment: "zoo".into(),
                                 child: Node {
-                                    route: Some(EndpointId(1)),
+                                    routes: vec![EndpointId(1)],
                                     ..Default::default()
                                 }
+                                .into()
                             },
                         ],
                         ..Default::default()
                     }
+                    .into()
                 }],
                 ..Default::default()
             }
@@ -242,20 +385,24 @@ mod tests {
     fn param() {
         let mut tree = Tree::default();
         let mut params = None;
-        tree.insert(b"/posts/:post", &mut params).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/posts/:post", &mut params)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/posts/".into(),
                     child: Node {
-                        param_segment: Some(Box::new(Node {
-                            route: Some(EndpointId(0)),
+                        param_segment: Some(Arc::new(Node {
+                            routes: vec![EndpointId(0)],
                             ..Default::default()
                         })),
                         ..Default::default()
-                    },
+                    }
+                    .into(),
                 }],
                 ..Default::default()
             }
@@ -270,26 +417,29 @@ mod tests {
         let mut params = None;
         tree.insert(b"/posts/:post/edit", &mut params)
             .unwrap()
-            .route = Some(EndpointId(0));
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/posts/".into(),
                     child: Node {
-                        param_segment: Some(Box::new(Node {
+                        param_segment: Some(Arc::new(Node {
                             static_segments: vec![StaticSegment {
                                 segment: "/edit".into(),
                                 child: Node {
-                                    route: Some(EndpointId(0)),
+                                    routes: vec![EndpointId(0)],
                                     ..Default::default()
-                                },
+                                }
+                                .into(),
                             }],
                             ..Default::default()
                         })),
                         ..Default::default()
-                    },
+                    }
+                    .into(),
                 }],
                 ..Default::default()
             }
@@ -302,12 +452,21 @@ mod tests {
     fn parameters() {
         let mut tree = Tree::default();
         let (mut p1, mut p2, mut p3) = (None, None, None);
-        tree.insert(b"/users/:id", &mut p1).unwrap().route = Some(EndpointId(0));
-        tree.insert(b"/users/:id/books", &mut p2).unwrap().route = Some(EndpointId(1));
-        tree.insert(b"/users/admin/books", &mut p3).unwrap().route = Some(EndpointId(2));
+        tree.insert(b"/users/:id", &mut p1)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/users/:id/books", &mut p2)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(b"/users/admin/books", &mut p3)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/users/".into(),
@@ -315,23 +474,26 @@ mod tests {
                         static_segments: vec![StaticSegment {
                             segment: "admin/books".into(),
                             child: Node {
-                                route: Some(EndpointId(2)),
+                                routes: vec![EndpointId(2)],
                                 ..Default::default()
-                            },
+                            }
+                            .into(),
                         }],
-                        param_segment: Some(Box::new(Node {
-                            route: Some(EndpointId(0)),
+                        param_segment: Some(Arc::new(Node {
+                            routes: vec![EndpointId(0)],
                             static_segments: vec![StaticSegment {
                                 segment: "/books".into(),
                                 child: Node {
-                                    route: Some(EndpointId(1)),
+                                    routes: vec![EndpointId(1)],
                                     ..Default::default()
-                                },
+                                }
+                                .into(),
                             }],
                             ..Default::default()
                         })),
                         ..Default::default()
-                    },
+                    }
+                    .into(),
                 }],
                 ..Default::default()
             }
@@ -346,10 +508,13 @@ mod tests {
     fn wildcard() {
         let mut tree = Tree::default();
         let mut params = None;
-        tree.insert(b"/static/*", &mut params).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/static/*", &mut params)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/static/".into(),
@@ -357,12 +522,14 @@ mod tests {
                         wildcard_segments: vec![WildcardSegment {
                             slug: "".into(),
                             child: Node {
-                                route: Some(EndpointId(0)),
+                                routes: vec![EndpointId(0)],
                                 ..Default::default()
-                            },
+                            }
+                            .into(),
                         }],
                         ..Default::default()
-                    },
+                    }
+                    .into(),
                 }],
                 ..Default::default()
             }
@@ -376,10 +543,11 @@ mod tests {
         let mut params = None;
         tree.insert(b"/static/*/index.html", &mut params)
             .unwrap()
-            .route = Some(EndpointId(0));
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/static/".into(),
@@ -387,12 +555,14 @@ mod tests {
                         wildcard_segments: vec![WildcardSegment {
                             slug: "/index.html".into(),
                             child: Node {
-                                route: Some(EndpointId(0)),
+                                routes: vec![EndpointId(0)],
                                 ..Default::default()
-                            },
+                            }
+                            .into(),
                         }],
                         ..Default::default()
-                    },
+                    }
+                    .into(),
                 }],
                 ..Default::default()
             }
@@ -405,11 +575,15 @@ mod tests {
         let mut tree = Tree::default();
         tree.insert(b"/static/*/index.html", &mut None)
             .unwrap()
-            .route = Some(EndpointId(0));
-        tree.insert(b"/static/*/index.js", &mut None).unwrap().route = Some(EndpointId(1));
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/static/*/index.js", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
 
         assert_eq!(
-            tree.root,
+            *tree.root,
             Node {
                 static_segments: vec![StaticSegment {
                     segment: "/static/".into(),
@@ -418,26 +592,163 @@ mod tests {
                             WildcardSegment {
                                 slug: "/index.html".into(),
                                 child: Node {
-                                    route: Some(EndpointId(0)),
+                                    routes: vec![EndpointId(0)],
                                     ..Default::default()
-                                },
+                                }
+                                .into(),
                             },
                             WildcardSegment {
                                 slug: "/index.js".into(),
                                 child: Node {
-                                    route: Some(EndpointId(1)),
+                                    routes: vec![EndpointId(1)],
                                     ..Default::default()
-                                },
+                                }
+                                .into(),
                             },
                         ],
                         ..Default::default()
-                    },
+                    }
+                    .into(),
                 }],
                 ..Default::default()
             }
         );
     }
 
+    #[test]
+    fn wildcard_segments_sorted_by_slug_length() {
+        let mut tree = Tree::default();
+        tree.insert(b"/*", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/*.html", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(b"/*/index.html", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+        tree.insert(b"/*.css", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(3));
+
+        let slugs: Vec<_> = tree.root.static_segments[0]
+            .child
+            .wildcard_segments
+            .iter()
+            .map(|s| &s.slug[..])
+            .collect();
+        assert_eq!(
+            slugs,
+            vec![&b"/index.html"[..], &b".html"[..], &b".css"[..], &b""[..]]
+        );
+    }
+
+    #[test]
+    fn constrained_param() {
+        let mut tree = Tree::default();
+        let mut names = None;
+        tree.insert(br"/archive/:date<\d{4}/\d{2}>", &mut names)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(br"/archive/:date<\d{4}/\d{2}>/:id", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(b"/archive/:id", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+
+        assert_eq!(names.unwrap().names, vec![b"date".to_vec()]);
+
+        let archive = &tree.root.static_segments[0].child;
+        assert_eq!(archive.constrained_segments.len(), 1);
+        let constrained = &archive.constrained_segments[0];
+        assert_eq!(constrained.pattern, r"\d{4}/\d{2}");
+        assert_eq!(constrained.child.routes, vec![EndpointId(0)]);
+        assert!(constrained.child.static_segments[0]
+            .child
+            .param_segment
+            .is_some());
+        assert_eq!(
+            archive.param_segment.as_ref().unwrap().routes,
+            vec![EndpointId(2)]
+        );
+    }
+
+    #[test]
+    fn parameters_with_constraint() {
+        let parse = |path: &'static [u8]| {
+            parse_parameter(path)
+                .ok()
+                .map(|p| (p.name, p.constraint, p.len))
+        };
+        assert_eq!(parse(b":id/books"), Some((&b"id"[..], None, 3)));
+        assert_eq!(
+            parse(br":d<\d+>/x"),
+            Some((&b"d"[..], Some(&br"\d+"[..]), 7))
+        );
+        assert_eq!(
+            parse(br":d<(?P<y>\d+)\>>"),
+            Some((&b"d"[..], Some(&br"(?P<y>\d+)\>"[..]), 16))
+        );
+        assert_eq!(parse(b":d<a"), None);
+        assert_eq!(parse(b":d<>"), None);
+        assert_eq!(parse(b":d<a>.html"), None);
+    }
+
+    #[test]
+    fn failcase_invalid_constraint() {
+        let mut tree = Tree::default();
+        assert!(tree.insert(b"/archive/:date<(>", &mut None).is_err());
+        assert!(tree.insert(b"/archive/:date<a", &mut None).is_err());
+    }
+
+    #[test]
+    fn insert_into_clone() {
+        let mut tree = Tree::default();
+        tree.insert(b"/users/:id", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/posts/:id", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        let mut cloned = tree.clone();
+        cloned
+            .insert(b"/users/:id/books", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+
+        // Only the nodes along the inserted path are copied. The static
+        // segments are sorted, so `posts/` comes before `users/`.
+        let children = |tree: &Tree| {
+            let root = &tree.root.static_segments[0].child;
+            (
+                root.static_segments[0].child.clone(),
+                root.static_segments[1].child.clone(),
+            )
+        };
+        let (posts, users) = children(&tree);
+        let (cloned_posts, cloned_users) = children(&cloned);
+        assert!(Arc::ptr_eq(&posts, &cloned_posts));
+        assert!(!Arc::ptr_eq(&users, &cloned_users));
+
+        assert!(tree.recognize(b"/users/1/books").route.is_none());
+        assert_eq!(
+            cloned.recognize(b"/users/1/books").route,
+            Some(EndpointId(2))
+        );
+    }
+
     #[test]
     fn failcase_invalid_param_indicator_position() {
         let mut tree = Tree::default();
@@ -0,0 +1,196 @@
+use super::{parse_parameter, ConstrainedSegment, Node, StaticSegment, Tree, WildcardSegment};
+use crate::endpoint::EndpointId;
+use std::fmt;
+
+impl Tree {
+    /// Renders this tree with indentation, using `path` to look up the original
+    /// path of each endpoint.
+    pub(crate) fn print<'p>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        path: &dyn Fn(EndpointId) -> Option<&'p str>,
+    ) -> fmt::Result {
+        let cx = PrintContext { path };
+        if !self.root.routes.is_empty() || self.root.scope.is_some() {
+            cx.print_node(f, 0, 0, "(root)", &self.root)?;
+        }
+        cx.print_children(f, 0, 0, &self.root)
+    }
+}
+
+struct PrintContext<'a, 'p> {
+    path: &'a dyn Fn(EndpointId) -> Option<&'p str>,
+}
+
+impl<'a, 'p> PrintContext<'a, 'p> {
+    /// Prints the children of `node`, which is reached after `params` parameters.
+    fn print_children(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        depth: usize,
+        params: usize,
+        node: &Node,
+    ) -> fmt::Result {
+        for StaticSegment { segment, child } in &node.static_segments {
+            let label = String::from_utf8_lossy(segment);
+            self.print_node(f, depth, params, &label, child)?;
+        }
+        for ConstrainedSegment { pattern, child, .. } in &node.constrained_segments {
+            let label = format!("{}<{}>", self.param_label(params, child), pattern);
+            self.print_node(f, depth, params + 1, &label, child)?;
+        }
+        if let Some(child) = &node.param_segment {
+            let label = self.param_label(params, child);
+            self.print_node(f, depth, params + 1, &label, child)?;
+        }
+        for WildcardSegment { slug, child } in &node.wildcard_segments {
+            let label = format!("*{}", String::from_utf8_lossy(slug));
+            self.print_node(f, depth, params, &label, child)?;
+        }
+        Ok(())
+    }
+
+    fn print_node(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        depth: usize,
+        params: usize,
+        label: &str,
+        node: &Node,
+    ) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", label, indent = depth * 2)?;
+        for &id in &node.routes {
+            self.print_endpoint(f, "route", id)?;
+        }
+        if let Some(id) = node.scope {
+            self.print_endpoint(f, "scope", id)?;
+        }
+        writeln!(f)?;
+        self.print_children(f, depth + 1, params, node)
+    }
+
+    /// Returns the label of the parameter at `position`, e.g. `:id`.
+    ///
+    /// The tree does not store the names of the parameters, so they are taken
+    /// from the paths of the endpoints below `child`. If these endpoints name
+    /// the parameter differently, all the names are listed, e.g. `:id|name`.
+    fn param_label(&self, position: usize, child: &Node) -> String {
+        let mut endpoints = vec![];
+        child.collect_endpoints(&mut endpoints);
+
+        let mut names: Vec<&str> = vec![];
+        for id in endpoints {
+            if let Some(name) = (self.path)(id).and_then(|path| param_name(path, position)) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        format!(":{}", names.join("|"))
+    }
+
+    fn print_endpoint(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        kind: &str,
+        id: EndpointId,
+    ) -> fmt::Result {
+        write!(f, "  [{} #{}", kind, id.0)?;
+        if let Some(path) = (self.path)(id) {
+            write!(f, " {}", path)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl Node {
+    fn collect_endpoints(&self, endpoints: &mut Vec<EndpointId>) {
+        endpoints.extend(&self.routes);
+        endpoints.extend(self.scope);
+        for s in &self.static_segments {
+            s.child.collect_endpoints(endpoints);
+        }
+        for s in &self.constrained_segments {
+            s.child.collect_endpoints(endpoints);
+        }
+        if let Some(child) = &self.param_segment {
+            child.collect_endpoints(endpoints);
+        }
+        for s in &self.wildcard_segments {
+            s.child.collect_endpoints(endpoints);
+        }
+    }
+}
+
+/// Returns the name of the parameter at `position` in the route pattern `path`.
+fn param_name(path: &str, position: usize) -> Option<&str> {
+    let mut rest = path;
+    let mut count = 0;
+    loop {
+        let start = rest.find([':', '*'])?;
+        if rest[start..].starts_with('*') {
+            return None;
+        }
+        let param = parse_parameter(&rest.as_bytes()[start..]).ok()?;
+        if count == position {
+            return std::str::from_utf8(param.name).ok();
+        }
+        count += 1;
+        rest = &rest[start + param.len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Print<'a>(&'a Tree, &'a [&'a str]);
+
+    impl fmt::Display for Print<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.print(f, &|id| self.1.get(id.0).cloned())
+        }
+    }
+
+    #[test]
+    fn print() {
+        let paths = [
+            "/users/:id",
+            "/users/:id/books",
+            "/users/admin/books",
+            "/static/*/index.html",
+            "/users/",
+        ];
+        let mut tree = Tree::default();
+        tree.insert(paths[0].as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(paths[1].as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(paths[2].as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+        tree.insert(paths[3].as_bytes(), &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(3));
+        tree.insert(paths[4].as_bytes(), &mut None).unwrap().scope = Some(EndpointId(4));
+
+        assert_eq!(
+            Print(&tree, &paths).to_string(),
+            "\
+/
+  static/
+    */index.html  [route #3 /static/*/index.html]
+  users/  [scope #4 /users/]
+    admin/books  [route #2 /users/admin/books]
+    :id  [route #0 /users/:id]
+      /books  [route #1 /users/:id/books]
+"
+        );
+    }
+}
@@ -1,126 +1,372 @@
 use super::{Node, StaticSegment, Tree, WildcardSegment};
-use crate::endpoint::EndpointId;
+use crate::{
+    endpoint::EndpointId,
+    explain::{Rejection, Step},
+};
 
 #[derive(Debug)]
 pub(crate) struct Recognize {
     pub(crate) route: Option<EndpointId>,
-    pub(crate) scope: Option<EndpointId>,
+    pub(crate) scopes: Vec<EndpointId>,
     pub(crate) params: Vec<(usize, usize)>,
     pub(crate) wildcard: Option<(usize, usize)>,
+    pub(crate) offset: usize,
     _p: (),
 }
 
 impl Tree {
     pub(crate) fn recognize<'p>(&'p self, path: &'p [u8]) -> Recognize {
-        let mut params = vec![];
-        let mut wildcard = None;
-        let mut scope = None;
+        self.recognize_inner(path, &|_| true, None, None)
+    }
 
-        let mut cx = RecognizeContext {
-            path,
-            offset: 0,
-            scope: &mut scope,
-            params: &mut params,
-            wildcard: &mut wildcard,
-        };
-        let node = cx.run(&self.root);
+    /// Recognizes `path`, only considering the routes for which `accept` returns `true`.
+    ///
+    /// If no route on a node is accepted, the search continues as if the node
+    /// had no routes.
+    pub(crate) fn recognize_with<'p>(
+        &'p self,
+        path: &'p [u8],
+        accept: &'p dyn Fn(EndpointId) -> bool,
+    ) -> Recognize {
+        self.recognize_inner(path, accept, None, None)
+    }
 
-        let route = if path.len() == cx.offset {
-            node.route
-        } else {
-            None
+    /// Recognizes `path` while recording the steps into `trace`.
+    ///
+    /// A route is accepted if `reject` returns `None`, and the reason of
+    /// each rejection is recorded as well.
+    pub(crate) fn recognize_traced<'p>(
+        &'p self,
+        path: &'p [u8],
+        reject: &'p dyn Fn(EndpointId) -> Option<Rejection>,
+        trace: &'p mut Vec<Step>,
+    ) -> Recognize {
+        self.recognize_inner(path, &|_| true, Some(reject), Some(trace))
+    }
+
+    /// Returns the routes in the subtree of the deepest node reached while recognizing `path`.
+    pub(crate) fn routes_near<'p>(&'p self, path: &'p [u8]) -> Vec<EndpointId> {
+        let mut cx = RecognizeContext::new(path, &|_| true, None, None);
+        let node = match cx.search(&self.root) {
+            Some((node, _)) => node,
+            None => cx.miss.expect("a failed search always records a miss").node,
         };
 
-        Recognize {
-            route,
-            scope,
-            params,
-            wildcard,
-            _p: (),
+        let mut routes = vec![];
+        node.collect_routes(&mut routes);
+        routes
+    }
+
+    fn recognize_inner<'p>(
+        &'p self,
+        path: &'p [u8],
+        accept: &'p dyn Fn(EndpointId) -> bool,
+        reject: Option<&'p dyn Fn(EndpointId) -> Option<Rejection>>,
+        trace: Option<&'p mut Vec<Step>>,
+    ) -> Recognize {
+        let mut cx = RecognizeContext::new(path, accept, reject, trace);
+        match cx.search(&self.root) {
+            Some((_, route)) => Recognize {
+                route: Some(route),
+                scopes: cx.scopes,
+                params: cx.params,
+                wildcard: cx.wildcard,
+                offset: cx.offset,
+                _p: (),
+            },
+            None => {
+                // Report the state of the first (highest-priority) branch
+                // that was explored, as if no backtracking had happened.
+                let miss = cx.miss.expect("a failed search always records a miss");
+                Recognize {
+                    route: None,
+                    scopes: miss.scopes,
+                    params: miss.params,
+                    wildcard: miss.wildcard,
+                    offset: miss.offset,
+                    _p: (),
+                }
+            }
+        }
+    }
+}
+
+impl Node {
+    fn collect_routes(&self, routes: &mut Vec<EndpointId>) {
+        routes.extend(&self.routes);
+        for s in &self.static_segments {
+            s.child.collect_routes(routes);
+        }
+        for s in &self.constrained_segments {
+            s.child.collect_routes(routes);
+        }
+        if let Some(child) = &self.param_segment {
+            child.collect_routes(routes);
+        }
+        for s in &self.wildcard_segments {
+            s.child.collect_routes(routes);
         }
     }
 }
 
+macro_rules! trace {
+    ($cx:expr, $step:expr) => {
+        if let Some(trace) = $cx.trace.as_mut() {
+            // A backtrack is only reported once another candidate is tried.
+            if let Some(offset) = $cx.backtrack.take() {
+                trace.push(Step::Backtrack { offset });
+            }
+            trace.push($step);
+        }
+    };
+}
+
+/// The state at the first dead end reached during the search.
 #[derive(Debug)]
-struct RecognizeContext<'a> {
-    path: &'a [u8],
+struct Miss<'n> {
+    node: &'n Node,
     offset: usize,
-    scope: &'a mut Option<EndpointId>,
-    params: &'a mut Vec<(usize, usize)>,
-    wildcard: &'a mut Option<(usize, usize)>,
+    scopes: Vec<EndpointId>,
+    params: Vec<(usize, usize)>,
+    wildcard: Option<(usize, usize)>,
 }
 
-impl<'a> RecognizeContext<'a> {
-    fn run<'n>(&mut self, mut current: &'n Node) -> &'n Node {
-        loop {
-            if let Some(scope) = current.scope {
-                self.scope.replace(scope);
-            }
+/// A depth-first search over the tree.
+///
+/// The children of a node are tried in the order of precedence (static segment,
+/// constrained parameters in registration order, parameter, and wildcard
+/// segments with the longest slug first), and the search backtracks when a
+/// branch does not lead to a route.
+struct RecognizeContext<'a, 'n> {
+    path: &'a [u8],
+    accept: &'a dyn Fn(EndpointId) -> bool,
+    reject: Option<&'a dyn Fn(EndpointId) -> Option<Rejection>>,
+    offset: usize,
+    scopes: Vec<EndpointId>,
+    params: Vec<(usize, usize)>,
+    wildcard: Option<(usize, usize)>,
+    trace: Option<&'a mut Vec<Step>>,
+    backtrack: Option<usize>,
+    miss: Option<Miss<'n>>,
+}
 
-            if self.path.len() <= self.offset {
-                return current;
-            }
+impl<'a, 'n> RecognizeContext<'a, 'n> {
+    fn new(
+        path: &'a [u8],
+        accept: &'a dyn Fn(EndpointId) -> bool,
+        reject: Option<&'a dyn Fn(EndpointId) -> Option<Rejection>>,
+        trace: Option<&'a mut Vec<Step>>,
+    ) -> Self {
+        Self {
+            path,
+            accept,
+            reject,
+            offset: 0,
+            scopes: vec![],
+            params: vec![],
+            wildcard: None,
+            trace,
+            backtrack: None,
+            miss: None,
+        }
+    }
 
-            if let Some(ch) = self.find_static_segment(current) {
-                current = ch;
-                continue;
+    /// Searches for a route below `node`, and returns it with the node where it terminates.
+    fn search(&mut self, node: &'n Node) -> Option<(&'n Node, EndpointId)> {
+        trace!(
+            self,
+            Step::Visit {
+                offset: self.offset,
+                routes: node.routes.clone(),
+                scope: node.scope,
             }
+        );
 
-            if let Some(ch) = &current.param_segment {
-                let end = self
-                    .path
-                    .iter()
-                    .skip(self.offset)
-                    .position(|&c| c == b'/')
-                    .map(|pos| self.offset + pos)
-                    .unwrap_or_else(|| self.path.len());
-                self.params.push((self.offset, end));
-                self.offset = end;
+        let num_scopes = self.scopes.len();
+        if let Some(scope) = node.scope {
+            self.scopes.push(scope);
+            trace!(self, Step::EnterScope(scope));
+        }
 
-                current = &*ch;
-                continue;
+        if self.path.len() <= self.offset {
+            if let Some(&route) = node.routes.iter().find(|&&id| self.accepts(id)) {
+                return Some((node, route));
             }
-
-            if let Some(ch) = self.find_wildcard_segment(current) {
-                return ch;
+        } else {
+            if let Some(found) = self.search_static_segments(node) {
+                return Some(found);
+            }
+            if let Some(found) = self.search_constrained_segments(node) {
+                return Some(found);
+            }
+            if let Some(found) = self.search_param_segment(node) {
+                return Some(found);
+            }
+            if let Some(found) = self.search_wildcard_segments(node) {
+                return Some(found);
             }
-
-            return current;
         }
+
+        self.record_miss(node);
+        self.scopes.truncate(num_scopes);
+        None
     }
 
-    fn find_static_segment<'n>(&mut self, current: &'n Node) -> Option<&'n Node> {
+    fn search_static_segments(&mut self, node: &'n Node) -> Option<(&'n Node, EndpointId)> {
+        let offset = self.offset;
         for StaticSegment {
             ref segment,
             ref child,
-        } in &current.static_segments
+        } in &node.static_segments
         {
-            if self.offset + segment.len() <= self.path.len()
-                && self.path[self.offset..self.offset + segment.len()] == segment[..]
-            {
+            let matched = offset + segment.len() <= self.path.len()
+                && self.path[offset..offset + segment.len()] == segment[..];
+            trace!(
+                self,
+                Step::Static {
+                    segment: String::from_utf8_lossy(segment).into_owned(),
+                    offset,
+                    matched,
+                }
+            );
+            if matched {
+                // The static segments of a node never share a common prefix,
+                // so at most one of them can match.
                 self.offset += segment.len();
-                return Some(child);
+                if let Some(found) = self.search(child) {
+                    return Some(found);
+                }
+                self.offset = offset;
+                self.backtrack = Some(offset);
+                break;
             }
         }
         None
     }
 
-    fn find_wildcard_segment<'n>(&mut self, current: &'n Node) -> Option<&'n Node> {
+    fn search_constrained_segments(&mut self, node: &'n Node) -> Option<(&'n Node, EndpointId)> {
+        let offset = self.offset;
+        for segment in &node.constrained_segments {
+            let ends = segment.ends(self.path, offset);
+            if ends.is_empty() {
+                trace!(
+                    self,
+                    Step::Constraint {
+                        pattern: segment.pattern.clone(),
+                        start: offset,
+                        end: offset,
+                        matched: false,
+                    }
+                );
+            }
+            for end in ends {
+                trace!(
+                    self,
+                    Step::Constraint {
+                        pattern: segment.pattern.clone(),
+                        start: offset,
+                        end,
+                        matched: true,
+                    }
+                );
+                self.params.push((offset, end));
+                self.offset = end;
+                if let Some(found) = self.search(&segment.child) {
+                    return Some(found);
+                }
+                self.params.pop();
+                self.offset = offset;
+                self.backtrack = Some(offset);
+            }
+        }
+        None
+    }
+
+    fn search_param_segment(&mut self, node: &'n Node) -> Option<(&'n Node, EndpointId)> {
+        let child = node.param_segment.as_ref()?;
+
+        let offset = self.offset;
+        let end = self
+            .path
+            .iter()
+            .skip(offset)
+            .position(|&c| c == b'/')
+            .map(|pos| offset + pos)
+            .unwrap_or(self.path.len());
+        self.params.push((offset, end));
+        trace!(self, Step::Param { start: offset, end });
+        self.offset = end;
+
+        if let Some(found) = self.search(child) {
+            return Some(found);
+        }
+
+        self.params.pop();
+        self.offset = offset;
+        self.backtrack = Some(offset);
+        None
+    }
+
+    fn search_wildcard_segments(&mut self, node: &'n Node) -> Option<(&'n Node, EndpointId)> {
+        let offset = self.offset;
         for WildcardSegment {
             ref slug,
             ref child,
-        } in &current.wildcard_segments
+        } in &node.wildcard_segments
         {
-            if self.offset + slug.len() <= self.path.len()
-                && self.path[self.path.len() - slug.len()..] == slug[..]
-            {
-                *self.wildcard = Some((self.offset, self.path.len() - slug.len()));
+            let matched = offset + slug.len() <= self.path.len() && self.path.ends_with(&slug[..]);
+            trace!(
+                self,
+                Step::Wildcard {
+                    slug: String::from_utf8_lossy(slug).into_owned(),
+                    start: offset,
+                    end: if matched {
+                        self.path.len() - slug.len()
+                    } else {
+                        self.path.len()
+                    },
+                    matched,
+                }
+            );
+            if matched {
+                self.wildcard = Some((offset, self.path.len() - slug.len()));
                 self.offset = self.path.len();
-                return Some(child);
+                if let Some(found) = self.search(child) {
+                    return Some(found);
+                }
+                self.wildcard = None;
+                self.offset = offset;
+                self.backtrack = Some(offset);
             }
         }
         None
     }
+
+    fn accepts(&mut self, route: EndpointId) -> bool {
+        let reject = match self.reject {
+            Some(reject) => reject,
+            None => return (self.accept)(route),
+        };
+        match reject(route) {
+            Some(reason) => {
+                trace!(self, Step::Rejected { route, reason });
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn record_miss(&mut self, node: &'n Node) {
+        if self.miss.is_none() {
+            self.miss = Some(Miss {
+                node,
+                offset: self.offset,
+                scopes: self.scopes.clone(),
+                params: self.params.clone(),
+                wildcard: self.wildcard,
+            });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -136,7 +382,10 @@ mod tests {
     #[test]
     fn root_node() {
         let mut tree = Tree::default();
-        tree.insert(b"/", &mut None).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(tree.recognize(b"/").route, Some(EndpointId(0)));
     }
@@ -144,7 +393,10 @@ mod tests {
     #[test]
     fn nested_url() {
         let mut tree = Tree::default();
-        tree.insert(b"/books/23/chapters", &mut None).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/books/23/chapters", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         assert_eq!(
             tree.recognize(b"/books/23/chapters").route,
@@ -159,8 +411,14 @@ mod tests {
     #[test]
     fn multiple_routes() {
         let mut tree = Tree::default();
-        tree.insert(b"/domains/mime", &mut None).unwrap().route = Some(EndpointId(0));
-        tree.insert(b"/domains/yours", &mut None).unwrap().route = Some(EndpointId(1));
+        tree.insert(b"/domains/mime", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/domains/yours", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
 
         assert_eq!(tree.recognize(b"/domains/mime").route, Some(EndpointId(0)));
         assert_eq!(tree.recognize(b"/domains/yours").route, Some(EndpointId(1)));
@@ -172,7 +430,10 @@ mod tests {
     #[test]
     fn single_param() {
         let mut tree = Tree::default();
-        tree.insert(b"/posts/:post", &mut None).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/posts/:post", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         let recognize = tree.recognize(b"/posts/42");
         assert_eq!(recognize.route, Some(EndpointId(0)));
@@ -182,7 +443,10 @@ mod tests {
     #[test]
     fn param_with_suffix() {
         let mut tree = Tree::default();
-        tree.insert(b"/posts/:post/edit", &mut None).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/posts/:post/edit", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         let recognize = tree.recognize(b"/posts/42/edit");
         assert_eq!(recognize.route, Some(EndpointId(0)));
@@ -196,7 +460,8 @@ mod tests {
         let mut tree = Tree::default();
         tree.insert(b"/:year/:month/:date", &mut None)
             .unwrap()
-            .route = Some(EndpointId(0));
+            .routes
+            .push(EndpointId(0));
 
         let recognize = tree.recognize(b"/2019/05/01");
         assert_eq!(recognize.route, Some(EndpointId(0)));
@@ -208,8 +473,14 @@ mod tests {
     #[test]
     fn param_with_static_segment() {
         let mut tree = Tree::default();
-        tree.insert(b"/posts/new", &mut None).unwrap().route = Some(EndpointId(0));
-        tree.insert(b"/posts/:post", &mut None).unwrap().route = Some(EndpointId(1));
+        tree.insert(b"/posts/new", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/posts/:post", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
 
         assert_eq!(tree.recognize(b"/posts/new").route, Some(EndpointId(0)));
 
@@ -221,7 +492,10 @@ mod tests {
     #[test]
     fn wildcard() {
         let mut tree = Tree::default();
-        tree.insert(b"/static/*", &mut None).unwrap().route = Some(EndpointId(0));
+        tree.insert(b"/static/*", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
 
         let recognize = tree.recognize(b"/static/path/to/index.html");
         assert_eq!(recognize.route, Some(EndpointId(0)));
@@ -233,7 +507,8 @@ mod tests {
         let mut tree = Tree::default();
         tree.insert(b"/static/*/index.html", &mut None)
             .unwrap()
-            .route = Some(EndpointId(0));
+            .routes
+            .push(EndpointId(0));
 
         let recognize = tree.recognize(b"/static/path/to/index.html");
         assert_eq!(recognize.route, Some(EndpointId(0)));
@@ -245,8 +520,12 @@ mod tests {
         let mut tree = Tree::default();
         tree.insert(b"/static/*/index.html", &mut None)
             .unwrap()
-            .route = Some(EndpointId(0));
-        tree.insert(b"/static/*.html", &mut None).unwrap().route = Some(EndpointId(1));
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/static/*.html", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
 
         assert_eq!(
             tree.recognize(b"/static/path/to/index.html").route,
@@ -258,12 +537,224 @@ mod tests {
         );
     }
 
+    #[test]
+    fn wildcards_by_slug_length() {
+        let mut tree = Tree::default();
+        tree.insert(b"/static/*.html", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/static/*/index.html", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        assert_eq!(
+            tree.recognize(b"/static/path/to/index.html").route,
+            Some(EndpointId(1))
+        );
+        assert_eq!(
+            tree.recognize(b"/static/about.html").route,
+            Some(EndpointId(0))
+        );
+    }
+
+    #[test]
+    fn backtrack_from_static_segment() {
+        let mut tree = Tree::default();
+        tree.insert(b"/posts/new", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/posts/:post/edit", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        let recognize = tree.recognize(b"/posts/new/edit");
+        assert_eq!(recognize.route, Some(EndpointId(1)));
+        assert_eq!(recognize.params, vec![(7, 10)]);
+    }
+
+    #[test]
+    fn backtrack_from_param() {
+        let mut tree = Tree::default();
+        tree.insert(b"/files/:name", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/files/*", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        assert_eq!(tree.recognize(b"/files/a").route, Some(EndpointId(0)));
+
+        let recognize = tree.recognize(b"/files/a/b");
+        assert_eq!(recognize.route, Some(EndpointId(1)));
+        assert!(recognize.params.is_empty());
+        assert_eq!(recognize.wildcard, Some((7, 10)));
+    }
+
+    #[test]
+    fn backtrack_from_wildcard() {
+        let mut tree = Tree::default();
+        tree.insert(b"/files/*/index.html", &mut None)
+            .unwrap()
+            .scope = Some(EndpointId(0));
+        tree.insert(b"/files/*.html", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        let recognize = tree.recognize(b"/files/a/index.html");
+        assert_eq!(recognize.route, Some(EndpointId(1)));
+        assert!(recognize.scopes.is_empty());
+        assert_eq!(recognize.wildcard, Some((7, 14)));
+    }
+
+    #[test]
+    fn miss_reports_first_branch() {
+        let mut tree = Tree::default();
+        tree.insert(b"/posts/new", &mut None).unwrap().scope = Some(EndpointId(0));
+        tree.insert(b"/posts/:post/edit", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        let recognize = tree.recognize(b"/posts/new/delete");
+        assert!(recognize.route.is_none());
+        assert_eq!(recognize.scopes, vec![EndpointId(0)]);
+        assert!(recognize.params.is_empty());
+        assert_eq!(recognize.offset, 10);
+    }
+
+    #[test]
+    fn multiple_routes_on_node() {
+        let mut tree = Tree::default();
+        tree.insert(b"/posts/:post", &mut None)
+            .unwrap()
+            .routes
+            .extend(vec![EndpointId(0), EndpointId(1)]);
+        tree.insert(b"/posts/*", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+
+        assert_eq!(tree.recognize(b"/posts/1").route, Some(EndpointId(0)));
+        assert_eq!(
+            tree.recognize_with(b"/posts/1", &|id| id != EndpointId(0))
+                .route,
+            Some(EndpointId(1))
+        );
+        assert_eq!(
+            tree.recognize_with(b"/posts/1", &|id| id == EndpointId(2))
+                .route,
+            Some(EndpointId(2))
+        );
+        assert!(tree.recognize_with(b"/posts/1", &|_| false).route.is_none());
+    }
+
+    #[test]
+    fn constrained_param() {
+        let mut tree = Tree::default();
+        tree.insert(br"/archive/:date<\d{4}/\d{2}/\d{2}>", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(br"/archive/:date<\d{4}/\d{2}/\d{2}>/:slug", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+
+        let recognize = tree.recognize(b"/archive/2019/05/01");
+        assert_eq!(recognize.route, Some(EndpointId(0)));
+        assert_eq!(recognize.params, vec![(9, 19)]);
+
+        let recognize = tree.recognize(b"/archive/2019/05/01/hello");
+        assert_eq!(recognize.route, Some(EndpointId(1)));
+        assert_eq!(recognize.params, vec![(9, 19), (20, 25)]);
+
+        assert!(tree.recognize(b"/archive/2019/05").route.is_none());
+        assert!(tree.recognize(b"/archive/2019/05/1").route.is_none());
+    }
+
+    #[test]
+    fn constrained_param_precedence() {
+        let mut tree = Tree::default();
+        tree.insert(b"/files/:name", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(br"/files/:id<\d+>", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(b"/files/new", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+
+        assert_eq!(tree.recognize(b"/files/42").route, Some(EndpointId(1)));
+        assert_eq!(tree.recognize(b"/files/a42").route, Some(EndpointId(0)));
+        assert_eq!(tree.recognize(b"/files/new").route, Some(EndpointId(2)));
+    }
+
+    #[test]
+    fn backtrack_from_constrained_param() {
+        let mut tree = Tree::default();
+        tree.insert(br"/docs/:path<[a-z/]+>/edit", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+
+        // The longest match "a/b/edit" leads nowhere, so shorter ones are tried.
+        let recognize = tree.recognize(b"/docs/a/b/edit");
+        assert_eq!(recognize.route, Some(EndpointId(0)));
+        assert_eq!(recognize.params, vec![(6, 9)]);
+    }
+
+    #[test]
+    fn matchers_in_order() {
+        use std::sync::Arc;
+
+        let mut tree = Tree::default();
+        tree.matchers.insert(
+            "digits".into(),
+            Arc::new(|s: &str| s.bytes().all(|b| b.is_ascii_digit())),
+        );
+        tree.matchers
+            .insert("short".into(), Arc::new(|s: &str| s.len() < 4));
+        tree.insert(b"/items/:id<digits>", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(0));
+        tree.insert(b"/items/:id<short>", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(1));
+        tree.insert(b"/items/:id<short>/:sub", &mut None)
+            .unwrap()
+            .routes
+            .push(EndpointId(2));
+
+        assert_eq!(tree.recognize(b"/items/12").route, Some(EndpointId(0)));
+        assert_eq!(tree.recognize(b"/items/abc").route, Some(EndpointId(1)));
+        assert!(tree.recognize(b"/items/abcde").route.is_none());
+
+        // a matcher only sees a single segment.
+        let recognize = tree.recognize(b"/items/ab/c");
+        assert_eq!(recognize.route, Some(EndpointId(2)));
+        assert_eq!(recognize.params, vec![(7, 9), (10, 11)]);
+    }
+
     #[test]
     fn scopes() {
         let mut tree = Tree::default();
         tree.insert(b"/path/to/index.html", &mut None) //
             .unwrap()
-            .route = Some(EndpointId(0));
+            .routes
+            .push(EndpointId(0));
 
         tree.insert(b"/path/", &mut None)
             .unwrap()
@@ -274,8 +765,11 @@ mod tests {
             .scope
             .replace(EndpointId(1));
 
-        assert_eq!(tree.recognize(b"/path/foo").scope, Some(EndpointId(0)));
-        assert_eq!(tree.recognize(b"/path/to/index").scope, Some(EndpointId(1)));
-        assert!(tree.recognize(b"/pattern").scope.is_none());
+        assert_eq!(tree.recognize(b"/path/foo").scopes, vec![EndpointId(0)]);
+        assert_eq!(
+            tree.recognize(b"/path/to/index").scopes,
+            vec![EndpointId(0), EndpointId(1)]
+        );
+        assert!(tree.recognize(b"/pattern").scopes.is_empty());
     }
 }
@@ -0,0 +1,242 @@
+//! Typed route definitions.
+//!
+//! # Example
+//!
+//! ```
+//! # use tsukuyomi_router::{Router, typed::Route};
+//! struct UserBook;
+//!
+//! impl Route for UserBook {
+//!     const PATH: &'static str = "/users/:id/books/:isbn";
+//!     type Params = (u64, String);
+//! }
+//!
+//! let mut router = Router::new();
+//! router.add_typed::<UserBook>("user_book")?;
+//!
+//! let recognize = router.recognize("/users/42/books/4-8222-8041-4");
+//! let (id, isbn) = recognize.typed::<UserBook>().unwrap()?;
+//! assert_eq!(id, 42);
+//! assert_eq!(isbn, "4-8222-8041-4");
+//!
+//! assert_eq!(UserBook::url(&(7, "123".into())), "/users/7/books/123");
+//! # Ok::<(), tsukuyomi_router::Error>(())
+//! ```
+
+use crate::{
+    endpoint::EndpointId, //
+    error::{ParamError, Result},
+    param::Params,
+    router::{Recognize, Router},
+    tree::parse_parameter,
+};
+use std::{fmt::Display, str::FromStr};
+
+/// A route whose path and parameter types are known at compile time.
+pub trait Route {
+    /// The path pattern of this route, in the same syntax as `Router::add_route`.
+    const PATH: &'static str;
+
+    /// The type of the parameters extracted from the path.
+    type Params: FromParams;
+
+    /// Builds a path for this route from the specified parameters.
+    ///
+    /// The values are inserted into the pattern as they are, without percent-encoding.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the number of values differs from the number
+    /// of parameters in `Self::PATH`.
+    fn url(params: &Self::Params) -> String
+    where
+        Self::Params: ToParams,
+    {
+        format_path(Self::PATH, params.to_params())
+    }
+}
+
+/// A type that can be extracted from the parameters of a path.
+///
+/// The parameters are ordered by their position in the path, and the wildcard
+/// parameter, if any, comes last.
+pub trait FromParams: Sized {
+    /// The number of parameters extracted by this type, if it is fixed.
+    ///
+    /// `Router::add_typed` checks this number against the parameters of the path.
+    const LEN: Option<usize> = None;
+
+    /// Extracts the value from the parameters.
+    ///
+    /// `params` is `None` if the route has no parameters.
+    fn from_params(params: Option<&Params<'_>>) -> Result<Self>;
+}
+
+/// A type that can be converted into the parameters of a path.
+///
+/// This is the inverse of `FromParams`.
+pub trait ToParams {
+    /// Returns the values of the parameters, in the order of their position in the path.
+    fn to_params(&self) -> Vec<String>;
+}
+
+impl FromParams for () {
+    const LEN: Option<usize> = Some(0);
+
+    fn from_params(_: Option<&Params<'_>>) -> Result<Self> {
+        Ok(())
+    }
+}
+
+impl ToParams for () {
+    fn to_params(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+fn parse_at<T>(params: Option<&Params<'_>>, i: usize) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let params = match params {
+        Some(params) if i == params.spans.len() && params.names.has_wildcard => {
+            return params.parse("*").map_err(Into::into)
+        }
+        Some(params) => params,
+        None => return Err(ParamError::missing(None, Some(i)).into()),
+    };
+    params.parse_at(i).map_err(Into::into)
+}
+
+macro_rules! impl_tuple {
+    ($len:expr; $($T:ident => $i:tt),*) => {
+        impl<$($T),*> FromParams for ($($T,)*)
+        where
+            $( $T: FromStr, $T::Err: Into<Box<dyn std::error::Error + Send + Sync>>, )*
+        {
+            const LEN: Option<usize> = Some($len);
+
+            fn from_params(params: Option<&Params<'_>>) -> Result<Self> {
+                Ok(($( parse_at::<$T>(params, $i)?, )*))
+            }
+        }
+
+        impl<$($T),*> ToParams for ($($T,)*)
+        where
+            $( $T: Display, )*
+        {
+            fn to_params(&self) -> Vec<String> {
+                vec![$( self.$i.to_string() ),*]
+            }
+        }
+    };
+}
+
+impl_tuple!(1; T1 => 0);
+impl_tuple!(2; T1 => 0, T2 => 1);
+impl_tuple!(3; T1 => 0, T2 => 1, T3 => 2);
+impl_tuple!(4; T1 => 0, T2 => 1, T3 => 2, T4 => 3);
+impl_tuple!(5; T1 => 0, T2 => 1, T3 => 2, T4 => 3, T5 => 4);
+
+/// Splits a path pattern into the static parts around its parameters.
+fn static_parts(pattern: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut rest = pattern;
+    while let Some(pos) = rest.find([':', '*']) {
+        parts.push(&rest[..pos]);
+        let end = if rest[pos..].starts_with(':') {
+            match parse_parameter(&rest.as_bytes()[pos..]) {
+                Ok(param) => pos + param.len,
+                Err(..) => rest[pos..].find('/').map_or(rest.len(), |end| pos + end),
+            }
+        } else {
+            pos + 1
+        };
+        rest = &rest[end..];
+    }
+    parts.push(rest);
+    parts
+}
+
+fn format_path(pattern: &str, values: Vec<String>) -> String {
+    let parts = static_parts(pattern);
+    assert_eq!(
+        values.len(),
+        parts.len() - 1,
+        "the number of values does not match the parameters of `{}`",
+        pattern
+    );
+
+    let mut path = String::with_capacity(pattern.len());
+    path.push_str(parts[0]);
+    for (value, part) in values.iter().zip(&parts[1..]) {
+        path.push_str(value);
+        path.push_str(part);
+    }
+    path
+}
+
+impl<T> Router<T> {
+    /// Adds a typed route to this router.
+    ///
+    /// An error is returned if `R::Params` does not have as many values as
+    /// the parameters of `R::PATH`.
+    pub fn add_typed<R: Route>(&mut self, data: T) -> Result<EndpointId> {
+        let num_params = static_parts(R::PATH).len() - 1;
+        match R::Params::LEN {
+            Some(len) if len != num_params => {
+                bail!(format!(
+                    "the typed parameters have {} value(s), but the path `{}` has {} parameter(s)",
+                    len,
+                    R::PATH,
+                    num_params
+                ));
+            }
+            _ => (),
+        }
+        self.add_route(R::PATH, data)
+    }
+}
+
+impl<'r, T> Recognize<'r, T> {
+    /// Extracts the typed parameters of `R` if the matched route is `R`.
+    ///
+    /// Returns `None` if no route matched or if the matched route has a path
+    /// different from `R::PATH`.
+    pub fn typed<R: Route>(&self) -> Option<Result<R::Params>> {
+        let (route, params) = self.route()?;
+        if route.path() != R::PATH {
+            return None;
+        }
+        Some(R::Params::from_params(params.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(format_path("/", vec![]), "/");
+        assert_eq!(
+            format_path("/users/:id/books", vec!["42".into()]),
+            "/users/42/books"
+        );
+        assert_eq!(
+            format_path("/:year/:month", vec!["2019".into(), "05".into()]),
+            "/2019/05"
+        );
+        assert_eq!(
+            format_path("/users/:id/*/index.html", vec!["42".into(), "a/b".into()]),
+            "/users/42/a/b/index.html"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of values does not match")]
+    fn format_missing_value() {
+        format_path("/:year/:month", vec!["2019".into()]);
+    }
+}
@@ -0,0 +1,161 @@
+//! Versioned routes.
+
+use crate::{
+    error::Result, //
+    guard::Input,
+    router::Router,
+};
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
+/// The location of the API version requested by a client.
+///
+/// The version source is set by `Router::set_version_source`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSource {
+    /// A leading path segment of the form `/v<N>`, e.g. `/v2/users/42`.
+    ///
+    /// The segment is removed if the rest of the path matches a versioned route.
+    /// Otherwise, the segment is a part of the path, so an unversioned route
+    /// such as `/health` is not reachable as `/v2/health`, whereas a route
+    /// registered as `/v2/health` is.
+    PathPrefix,
+    /// The value of the specified request header, e.g. `Accept`.
+    ///
+    /// The value may be a bare number (`2`), contain a `version=<N>` parameter
+    /// (`application/json; version=2`), or contain a `v<N>` token
+    /// (`application/vnd.example.v2+json`).
+    Header(String),
+    /// The value of the specified query parameter, e.g. `?api-version=2`.
+    Query(String),
+}
+
+impl<T> Router<T> {
+    /// Sets where the requested API version is read from.
+    ///
+    /// If no version source is set, or the request does not specify a version,
+    /// the latest version of each versioned route is used.
+    pub fn set_version_source(&mut self, source: VersionSource) {
+        self.version_source = Some(source);
+    }
+
+    /// Returns the version source of this router, if any.
+    pub fn version_source(&self) -> Option<&VersionSource> {
+        self.version_source.as_ref()
+    }
+
+    /// Returns the path to be recognized and the version requested by the input.
+    pub(crate) fn requested_version<'a>(&self, input: &Input<'a>) -> (&'a str, Option<u32>) {
+        match self.version_source {
+            Some(VersionSource::PathPrefix) => strip_version_prefix(input.path()),
+            Some(VersionSource::Header(ref name)) => {
+                (input.path(), input.header(name).and_then(parse_version))
+            }
+            Some(VersionSource::Query(ref key)) => {
+                (input.path(), input.query_param(key).and_then(parse_version))
+            }
+            None => (input.path(), None),
+        }
+    }
+}
+
+/// Converts the provided range to an inclusive range of versions.
+pub(crate) fn to_inclusive<R>(versions: R) -> Result<RangeInclusive<u32>>
+where
+    R: RangeBounds<u32>,
+{
+    let start = match versions.start_bound() {
+        Bound::Included(&start) => Some(start),
+        Bound::Excluded(&start) => start.checked_add(1),
+        Bound::Unbounded => Some(0),
+    };
+    let end = match versions.end_bound() {
+        Bound::Included(&end) => Some(end),
+        Bound::Excluded(&end) => end.checked_sub(1),
+        Bound::Unbounded => Some(u32::MAX),
+    };
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => Ok(start..=end),
+        _ => {
+            bail!("the range of versions is empty");
+        }
+    }
+}
+
+/// Splits the leading `/v<N>` segment off the path.
+pub(crate) fn strip_version_prefix(path: &str) -> (&str, Option<u32>) {
+    let rest = match path.strip_prefix("/v") {
+        Some(rest) => rest,
+        None => return (path, None),
+    };
+    let end = rest.find('/').unwrap_or(rest.len());
+    match rest[..end].parse() {
+        Ok(version) if rest[..end].bytes().all(|b| b.is_ascii_digit()) => {
+            let rest = &rest[end..];
+            (if rest.is_empty() { "/" } else { rest }, Some(version))
+        }
+        _ => (path, None),
+    }
+}
+
+/// Extracts a version number from a header value.
+pub(crate) fn parse_version(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if let Ok(version) = value.parse() {
+        return Some(version);
+    }
+
+    let leading_digits = |s: &str| {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        s[..end].parse().ok()
+    };
+
+    if let Some(pos) = value.find("version=") {
+        return leading_digits(&value[pos + "version=".len()..]);
+    }
+
+    value.char_indices().find_map(|(i, c)| {
+        let at_boundary = value[..i]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !c.is_ascii_alphanumeric());
+        if (c == 'v' || c == 'V') && at_boundary {
+            leading_digits(&value[i + 1..])
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inclusive_ranges() {
+        assert_eq!(to_inclusive(1..=3).unwrap(), 1..=3);
+        assert_eq!(to_inclusive(1..3).unwrap(), 1..=2);
+        assert_eq!(to_inclusive(2..).unwrap(), 2..=u32::MAX);
+        assert_eq!(to_inclusive(..=4).unwrap(), 0..=4);
+        assert!(to_inclusive(3..3).is_err());
+        assert!(to_inclusive(..0).is_err());
+    }
+
+    #[test]
+    fn version_prefix() {
+        assert_eq!(strip_version_prefix("/v2/users"), ("/users", Some(2)));
+        assert_eq!(strip_version_prefix("/v10"), ("/", Some(10)));
+        assert_eq!(strip_version_prefix("/videos/1"), ("/videos/1", None));
+        assert_eq!(strip_version_prefix("/v+1/users"), ("/v+1/users", None));
+        assert_eq!(strip_version_prefix("/users"), ("/users", None));
+    }
+
+    #[test]
+    fn header_version() {
+        assert_eq!(parse_version("2"), Some(2));
+        assert_eq!(parse_version("v3"), Some(3));
+        assert_eq!(parse_version("application/json; version=4"), Some(4));
+        assert_eq!(parse_version("application/vnd.example.v5+json"), Some(5));
+        assert_eq!(parse_version("application/json"), None);
+        assert_eq!(parse_version("dev1"), None);
+    }
+}
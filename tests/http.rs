@@ -0,0 +1,156 @@
+#![cfg(feature = "http")]
+
+use http::Request;
+use tsukuyomi_router::{EndpointId, Params, Router, VersionSource};
+
+#[test]
+fn recognize_request() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    let post = router.add_route("/posts/:post", "the_post")?;
+    router.set_methods(post, vec!["GET", "HEAD"])?;
+    let admin = router.add_route("/admin", "admin")?;
+    router.set_host(admin, "admin.example.com")?;
+
+    let request = Request::get("/posts/12").body(()).unwrap();
+    let res = router.recognize_request(&request);
+    assert_eq!(res.route().map(|(r, _)| r.id()), Some(post));
+    assert!(!res.is_method_not_allowed());
+
+    let request = Request::delete("/posts/12").body(()).unwrap();
+    let res = router.recognize_request(&request);
+    assert!(res.route().is_none());
+    assert!(res.is_method_not_allowed());
+
+    let request = Request::get("http://admin.example.com:8080/admin")
+        .body(())
+        .unwrap();
+    let res = router.recognize_request(&request);
+    assert_eq!(res.route().map(|(r, _)| r.id()), Some(admin));
+
+    let request = Request::get("/admin")
+        .header("host", "www.example.com")
+        .body(())
+        .unwrap();
+    let res = router.recognize_request(&request);
+    assert!(res.route().is_none());
+    assert!(!res.is_method_not_allowed());
+
+    Ok(())
+}
+
+#[test]
+fn route_request() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    let post = router.add_route("/posts/:post", "the_post")?;
+    let index = router.add_route("/", "index")?;
+
+    let mut request = Request::get("/posts/12").body(()).unwrap();
+    assert_eq!(router.route_request(&mut request), Some(post));
+    assert_eq!(request.extensions().get::<EndpointId>(), Some(&post));
+    let params = request.extensions().get::<Params<'static>>().unwrap();
+    assert_eq!(params.name("post"), Some("12"));
+
+    let mut request = Request::get("/").body(()).unwrap();
+    assert_eq!(router.route_request(&mut request), Some(index));
+    assert!(request.extensions().get::<Params<'static>>().is_none());
+
+    let mut request = Request::get("/users").body(()).unwrap();
+    assert_eq!(router.route_request(&mut request), None);
+    assert!(request.extensions().get::<EndpointId>().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn versioned_request() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_versioned("/users/:id", 1..3, "users_v1")?;
+    router.add_versioned("/users/:id", 3.., "users_v3")?;
+
+    let recognize = |router: &Router<&'static str>, request: Request<()>| {
+        let res = router.recognize_request(&request);
+        (res.route().map(|(r, _)| *r.data()), res.version())
+    };
+
+    router.set_version_source(VersionSource::Header("accept".into()));
+    let request = Request::get("/users/1")
+        .header("accept", "application/vnd.example.v2+json")
+        .body(())
+        .unwrap();
+    assert_eq!(recognize(&router, request), (Some("users_v1"), Some(2)));
+
+    router.set_version_source(VersionSource::Query("api-version".into()));
+    let request = Request::get("/users/1?api-version=4").body(()).unwrap();
+    assert_eq!(recognize(&router, request), (Some("users_v3"), Some(4)));
+    let request = Request::get("/users/1").body(()).unwrap();
+    assert_eq!(recognize(&router, request), (Some("users_v3"), Some(3)));
+
+    router.set_version_source(VersionSource::PathPrefix);
+    let request = Request::get("/v1/users/1").body(()).unwrap();
+    assert_eq!(recognize(&router, request), (Some("users_v1"), Some(1)));
+
+    Ok(())
+}
+
+#[test]
+fn guarded_request() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::guard;
+
+    let mut router = Router::new();
+    let get = router.add_route("/posts", "list")?;
+    router.set_methods(get, vec!["GET"])?;
+    let json = router.add_route("/posts", "create_json")?;
+    router.set_methods(json, vec!["POST"])?;
+    router.add_guard(json, guard::header("content-type", "application/json"))?;
+    let form = router.add_route("/posts", "create_form")?;
+    router.set_methods(form, vec!["POST"])?;
+
+    let data = |request: Request<()>| {
+        let res = router.recognize_request(&request);
+        (
+            res.route().map(|(r, _)| *r.data()),
+            res.is_method_not_allowed(),
+        )
+    };
+
+    let request = Request::get("/posts").body(()).unwrap();
+    assert_eq!(data(request), (Some("list"), false));
+
+    let request = Request::post("/posts")
+        .header("content-type", "application/json")
+        .body(())
+        .unwrap();
+    assert_eq!(data(request), (Some("create_json"), false));
+
+    let request = Request::post("/posts")
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(())
+        .unwrap();
+    assert_eq!(data(request), (Some("create_form"), false));
+
+    let request = Request::delete("/posts").body(()).unwrap();
+    assert_eq!(data(request), (None, true));
+
+    Ok(())
+}
+
+#[test]
+fn request_query() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::guard;
+
+    let mut router = Router::new();
+    let search = router.add_route("/posts", "search")?;
+    router.add_guard(search, guard::query("q"))?;
+    router.add_route("/posts", "list")?;
+
+    let request = Request::get("/posts?q=rust&page=2").body(()).unwrap();
+    let res = router.recognize_request(&request);
+    assert_eq!(res.route().map(|(r, _)| *r.data()), Some("search"));
+    assert_eq!(res.query().get("page"), Some("2"));
+
+    let request = Request::get("/posts").body(()).unwrap();
+    let res = router.recognize_request(&request);
+    assert_eq!(res.route().map(|(r, _)| *r.data()), Some("list"));
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "tower")]
+
+use futures_executor::block_on;
+use http::{header::ALLOW, Method, Request, Response, StatusCode};
+use std::{
+    convert::Infallible,
+    future::{ready, Ready},
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+use tsukuyomi_router::{
+    service::{RouterLayer, RouterService},
+    EndpointId, Params, Router,
+};
+
+#[derive(Clone)]
+struct Mock(&'static str);
+
+impl Service<Request<()>> for Mock {
+    type Response = Response<String>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<()>) -> Self::Future {
+        let id = request.extensions().get::<EndpointId>().is_some();
+        let param = request
+            .extensions()
+            .get::<Params<'static>>()
+            .and_then(|params| params.get(0).map(ToOwned::to_owned));
+        ready(Ok(Response::new(format!(
+            "{} id={} param={}",
+            self.0,
+            id,
+            param.unwrap_or_default()
+        ))))
+    }
+}
+
+fn call<S>(service: &mut S, method: Method, uri: &str) -> Response<String>
+where
+    S: Service<Request<()>, Response = Response<String>, Error = Infallible>,
+{
+    let request = Request::builder().method(method).uri(uri).body(()).unwrap();
+    block_on(service.call(request)).unwrap()
+}
+
+#[test]
+fn dispatch() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    let post = router.add_route("/posts/:post", Mock("post"))?;
+    router.set_methods(post, vec!["GET"])?;
+    let delete = router.add_route("/posts/:post", Mock("delete_post"))?;
+    router.set_methods(delete, vec!["DELETE", "GET"])?;
+    router.add_scope("/posts/", Mock("posts_scope"))?;
+    router.add_route("/", Mock("index"))?;
+
+    let mut service = RouterService::new(router);
+
+    let response = call(&mut service, Method::GET, "/posts/42");
+    assert_eq!(response.body(), "post id=true param=42");
+
+    let response = call(&mut service, Method::GET, "/posts/42/edit");
+    assert_eq!(response.body(), "posts_scope id=true param=");
+
+    let response = call(&mut service, Method::POST, "/posts/42");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()[ALLOW], "GET, DELETE");
+    assert_eq!(response.body(), "");
+
+    let response = call(&mut service, Method::GET, "/users");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[test]
+fn custom_fallbacks() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    let index = router.add_route("/", Mock("index"))?;
+    router.set_methods(index, vec!["GET"])?;
+
+    let mut service = RouterService::new(router)
+        .not_found(Mock("not_found"))
+        .method_not_allowed(Mock("method_not_allowed"));
+
+    let response = call(&mut service, Method::GET, "/users");
+    assert_eq!(response.body(), "not_found id=false param=");
+
+    let response = call(&mut service, Method::PUT, "/");
+    assert_eq!(response.body(), "method_not_allowed id=false param=");
+
+    Ok(())
+}
+
+#[test]
+fn router_fallbacks() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/api/posts", Mock("posts"))?;
+    let api = router.add_scope("/api/", Mock("api"))?;
+    router.set_scope_fallback(api, Mock("api_fallback"))?;
+    router.set_fallback(Mock("fallback"))?;
+
+    let mut service = RouterService::new(router).not_found(Mock("not_found"));
+
+    let response = call(&mut service, Method::GET, "/api/users");
+    assert_eq!(response.body(), "api_fallback id=true param=");
+
+    let response = call(&mut service, Method::GET, "/users");
+    assert_eq!(response.body(), "fallback id=true param=");
+
+    Ok(())
+}
+
+#[test]
+fn layer() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/posts/:post", Mock("post"))?;
+
+    let mut service = RouterLayer::new(router).layer(Mock("inner"));
+
+    let response = call(&mut service, Method::GET, "/posts/42");
+    assert_eq!(response.body(), "post id=true param=42");
+
+    let response = call(&mut service, Method::GET, "/users");
+    assert_eq!(response.body(), "inner id=false param=");
+
+    Ok(())
+}
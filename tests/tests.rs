@@ -108,3 +108,1330 @@ fn scope() -> tsukuyomi_router::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn route_table() -> tsukuyomi_router::Result<()> {
+    let table = "\
+# kind  path          handler      options
+route   /users/:id    users::show  name=user methods=GET,HEAD host=example.com
+
+scope   /api/         api
+fallback /api/        api::not_found
+fallback *            not_found
+";
+    let router = Router::from_table(table.as_bytes())?;
+
+    let res = router.recognize("/users/42");
+    let (route, _) = res.route().expect("no route");
+    assert_eq!(route.data(), "users::show");
+    assert_eq!(route.name(), Some("user"));
+    assert_eq!(route.methods(), &["GET".to_owned(), "HEAD".to_owned()]);
+    assert_eq!(route.host(), Some("example.com"));
+    assert_eq!(
+        router.endpoint_by_name("user").map(|e| e.id()),
+        Some(route.id())
+    );
+
+    let res = router.recognize("/api/foo");
+    assert_eq!(res.scope().map(|(s, _)| s.data().as_str()), Some("api"));
+    assert_eq!(
+        res.fallback().map(|f| f.data().as_str()),
+        Some("api::not_found")
+    );
+    assert_eq!(
+        router
+            .recognize("/foo")
+            .fallback()
+            .map(|f| f.data().as_str()),
+        Some("not_found")
+    );
+
+    let mut dumped = vec![];
+    router.write_table(&mut dumped).unwrap();
+    let restored = Router::from_table(&dumped[..])?;
+    let mut dumped2 = vec![];
+    restored.write_table(&mut dumped2).unwrap();
+    assert_eq!(dumped, dumped2);
+    assert_eq!(
+        String::from_utf8(dumped).unwrap(),
+        "route /users/:id users::show name=user methods=GET,HEAD host=example.com\n\
+         scope /api/ api\n\
+         fallback /api/ api::not_found\n\
+         fallback * not_found\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn route_table_errors() {
+    let table = "route /users/:id users\nroute /posts/:po:st posts\n";
+    let err = Router::from_table(table.as_bytes()).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+    assert!(std::error::Error::source(&err).is_some());
+
+    let table = "route /a a name=foo\nroute /b b name=foo\n";
+    let err = Router::from_table(table.as_bytes()).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+
+    let table = "route /a\n";
+    assert_eq!(
+        Router::from_table(table.as_bytes()).unwrap_err().line(),
+        Some(1)
+    );
+
+    let table = "fallback /a/ a\n";
+    assert_eq!(
+        Router::from_table(table.as_bytes()).unwrap_err().line(),
+        Some(1)
+    );
+
+    let table = "resource /a a\n";
+    assert_eq!(
+        Router::from_table(table.as_bytes()).unwrap_err().line(),
+        Some(1)
+    );
+
+    for table in &[
+        "route /a a\nroute /b b name=b name=c\n",
+        "route /a a\nroute /b b versions=1.. versions=2..\n",
+        "route /a a\nroute /b b methods=\n",
+        "route /a a\nroute /b b methods=,\n",
+        "route /a a\nroute /b b host=\n",
+        "route /a a\nroute /b b name\n",
+    ] {
+        let err = Router::from_table(table.as_bytes()).unwrap_err();
+        assert_eq!(err.line(), Some(2), "{}", table);
+    }
+    let table = "route /a a name=a name=b\n";
+    assert_eq!(
+        Router::from_table(table.as_bytes())
+            .unwrap_err()
+            .to_string(),
+        "line 1: the option `name` is specified more than once"
+    );
+    let table = "route /a a methods=\n";
+    assert_eq!(
+        Router::from_table(table.as_bytes())
+            .unwrap_err()
+            .to_string(),
+        "line 1: the option `methods` must not be empty"
+    );
+
+    let table = "fallback * not_found\nscope * all\n";
+    assert!(Router::from_table(table.as_bytes()).is_ok());
+    let table = "scope * all\nfallback * not_found\n";
+    assert_eq!(
+        Router::from_table(table.as_bytes()).unwrap_err().line(),
+        Some(2)
+    );
+}
+
+#[test]
+fn write_table_errors() -> tsukuyomi_router::Result<()> {
+    let write = |router: &Router<&str>| router.write_table(vec![]).map_err(|err| err.to_string());
+
+    let mut router = Router::new();
+    let id = router.add_route("/users/:id", "users::show")?;
+    assert!(write(&router).is_ok());
+
+    router.set_name(id, "the user")?;
+    assert_eq!(
+        write(&router).unwrap_err(),
+        "the name \"the user\" cannot be written"
+    );
+    router.set_name(id, "user")?;
+
+    router.set_methods(id, vec!["GET", "PO ST"])?;
+    assert!(write(&router).is_err());
+    router.set_methods(id, vec!["GET,HEAD"])?;
+    assert!(write(&router).is_err());
+    router.set_methods(id, vec!["GET"])?;
+
+    router.set_host(id, "example .com")?;
+    assert!(write(&router).is_err());
+    router.set_host(id, "example.com")?;
+    assert!(write(&router).is_ok());
+
+    let mut router = Router::new();
+    router.add_route("/my files/*", "files")?;
+    assert_eq!(
+        write(&router).unwrap_err(),
+        "the path \"/my files/*\" cannot be written"
+    );
+
+    let mut router = Router::new();
+    router.set_fallback("not_found")?;
+    let all = router.add_scope("*", "all")?;
+    assert!(write(&router).is_ok());
+    router.set_scope_fallback(all, "all::not_found")?;
+    assert!(write(&router).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn print_tree() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/posts/:post", "the_post")?;
+    router.add_route("/posts/create", "create_post")?;
+    router.add_route("/*", "catch_all")?;
+    router.add_scope("/posts/", "posts")?;
+    router.add_route("/posts/:id/comments", "comments")?;
+
+    assert_eq!(
+        router.print_tree().to_string(),
+        "\
+/
+  posts/  [scope #3 /posts/]
+    create  [route #1 /posts/create]
+    :post|id  [route #0 /posts/:post]
+      /comments  [route #4 /posts/:id/comments]
+  *  [route #2 /*]
+"
+    );
+
+    assert_eq!(
+        router
+            .routes()
+            .into_iter()
+            .map(|r| r.path())
+            .collect::<Vec<_>>(),
+        vec!["/*", "/posts/:id/comments", "/posts/:post", "/posts/create"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn to_dot() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/posts/:post", "the_post")?;
+    router.add_route("/\"quoted\"", "quoted")?;
+
+    let dot = router.to_dot();
+    assert!(dot.starts_with("digraph router {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("[label=\":\", style=dashed];"));
+    assert!(dot.contains("[label=\"route #0 /posts/:post\", style=bold];"));
+    assert!(dot.contains("[label=\"route #1 /\\\"quoted\\\"\", style=bold];"));
+
+    Ok(())
+}
+
+#[test]
+fn explain() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::explain::{Outcome, Step};
+
+    let mut router = Router::new();
+    let post = router.add_route("/posts/:post", "the_post")?;
+    router.add_route("/posts/:post/edit", "edit_post")?;
+    let posts = router.add_scope("/posts/", "posts")?;
+
+    let explain = router.explain("/posts/12");
+    assert_eq!(explain.outcome(), Outcome::Matched(post));
+    assert!(explain.steps().contains(&Step::EnterScope(posts)));
+    assert!(explain.steps().contains(&Step::Param { start: 7, end: 9 }));
+
+    let explain = router.explain("/posts/12/delete");
+    assert_eq!(explain.outcome(), Outcome::Unmatched { offset: 9 });
+    assert!(explain.steps().contains(&Step::Static {
+        segment: "/edit".into(),
+        offset: 9,
+        matched: false,
+    }));
+    assert_eq!(
+        explain.to_string(),
+        "\
+recognize \"/posts/12/delete\"
+  try \"/posts/12/delete\"
+  visit node at 0
+    static \"/posts/\" at 0: matched
+  visit node at 7 [scope #2 /posts/]
+    enter scope #2 /posts/
+    param \"12\" (7..9)
+  visit node at 9 [route #0 /posts/:post]
+    static \"/edit\" at 9: rejected
+no segment matches the remaining path \"/delete\" at 9
+"
+    );
+
+    assert_eq!(router.explain("/posts/").outcome(), Outcome::NoRoute);
+    assert_eq!(
+        router.explain("/pos").outcome(),
+        Outcome::Unmatched { offset: 0 }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn explain_pipeline() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::{
+        explain::{Outcome, Rejection, Step},
+        guard::{self, Input},
+        VersionSource,
+    };
+
+    let mut router = Router::new();
+    router.set_version_source(VersionSource::PathPrefix);
+    router.add_extension("json")?;
+    let v2 = router.add_versioned("/users/:id", 2.., "user_v2")?;
+    let v1 = router.add_route("/v1/users/:id", "user_v1")?;
+    let files = router.add_route("/files/*.txt", "files")?;
+    let admin = router.add_route("/admin", "admin")?;
+    router.add_guard(admin, guard::header("x-admin", "1"))?;
+
+    let explain = router.explain("/v2/users/1.json");
+    assert_eq!(explain.outcome(), Outcome::Matched(v2));
+    assert!(explain.steps().contains(&Step::Start {
+        path: "/users/1.json".into(),
+        version: Some(2),
+    }));
+    assert!(explain.steps().contains(&Step::Start {
+        path: "/users/1".into(),
+        version: Some(2),
+    }));
+
+    let explain = router.explain("/v1/users/1");
+    assert_eq!(explain.outcome(), Outcome::Matched(v1));
+    assert!(explain.steps().contains(&Step::Rejected {
+        route: v2,
+        reason: Rejection::Version,
+    }));
+    assert!(explain.steps().contains(&Step::Start {
+        path: "/v1/users/1".into(),
+        version: None,
+    }));
+
+    let explain = router.explain("/admin");
+    assert_eq!(explain.outcome(), Outcome::NoRoute);
+    assert!(explain.steps().contains(&Step::Rejected {
+        route: admin,
+        reason: Rejection::Guard,
+    }));
+    let input = Input::new("/admin").with_header("x-admin", "1");
+    assert_eq!(
+        router.explain_input(&input).outcome(),
+        Outcome::Matched(admin)
+    );
+
+    router.set_methods(admin, ["GET"])?;
+    let input = input.with_method("POST");
+    assert!(router
+        .explain_input(&input)
+        .to_string()
+        .contains("    reject route #3 /admin: method not allowed\n"));
+
+    let explain = router.explain("/files/a/b.txt");
+    assert_eq!(explain.outcome(), Outcome::Matched(files));
+    assert!(explain
+        .to_string()
+        .contains("    wildcard \"*.txt\": matched \"a/b\" (7..10)\n"));
+
+    Ok(())
+}
+
+#[test]
+fn suggest() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/users/:id", "user")?;
+    router.add_route("/users/:id/books", "user_books")?;
+    router.add_route("/posts/:post", "post")?;
+    router.add_route("/static/*.html", "static")?;
+    router.add_scope("/users/", "users")?;
+
+    let suggest = |path: &str, n: usize| -> Vec<&str> {
+        router
+            .suggest(path, n)
+            .into_iter()
+            .map(|e| e.path())
+            .collect()
+    };
+
+    assert_eq!(suggest("/usres/42", 1), vec!["/users/:id"]);
+    assert_eq!(
+        suggest("/users/42/book", 2),
+        vec!["/users/:id/books", "/users/:id"]
+    );
+    assert_eq!(suggest("/static/a/b/index.htm", 1), vec!["/static/*.html"]);
+    assert_eq!(suggest("/", 10).len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn typed() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::typed::Route;
+
+    struct Post;
+    impl Route for Post {
+        const PATH: &'static str = "/posts/:post";
+        type Params = (u32,);
+    }
+
+    struct Asset;
+    impl Route for Asset {
+        const PATH: &'static str = "/assets/:version/*";
+        type Params = (String, String);
+    }
+
+    struct Index;
+    impl Route for Index {
+        const PATH: &'static str = "/";
+        type Params = ();
+    }
+
+    let mut router = Router::new();
+    router.add_typed::<Post>("post")?;
+    router.add_typed::<Asset>("asset")?;
+    router.add_typed::<Index>("index")?;
+
+    assert_eq!(
+        router.recognize("/posts/12").typed::<Post>().unwrap()?,
+        (12,)
+    );
+    assert!(router.recognize("/posts/12").typed::<Asset>().is_none());
+    assert!(router
+        .recognize("/posts/foo")
+        .typed::<Post>()
+        .unwrap()
+        .is_err());
+    assert_eq!(
+        router
+            .recognize("/assets/v1/css/style.css")
+            .typed::<Asset>()
+            .unwrap()?,
+        ("v1".to_owned(), "css/style.css".to_owned())
+    );
+    router.recognize("/").typed::<Index>().unwrap()?;
+
+    assert_eq!(Post::url(&(3,)), "/posts/3");
+    assert_eq!(
+        Asset::url(&("v2".into(), "js/app.js".into())),
+        "/assets/v2/js/app.js"
+    );
+    assert_eq!(Index::url(&()), "/");
+
+    struct Mismatched;
+    impl Route for Mismatched {
+        const PATH: &'static str = "/users/:user/posts/:post";
+        type Params = (u32,);
+    }
+    assert!(router.add_typed::<Mismatched>("mismatched").is_err());
+    assert!(router.recognize("/users/1/posts/2").route().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn params_iter() -> tsukuyomi_router::Result<()> {
+    use std::collections::HashMap;
+
+    let mut router = Router::new();
+    router.add_route("/users/:user/posts/:post/*", "attachment")?;
+    router.add_route("/users/:user", "user")?;
+
+    let res = router.recognize("/users/alice/posts/12/files/a.png");
+    let (_, params) = res.route().unwrap();
+    let params = params.unwrap();
+    assert_eq!(params.len(), 3);
+    assert!(!params.is_empty());
+    assert_eq!(
+        params.names().collect::<Vec<_>>(),
+        vec!["user", "post", "*"]
+    );
+    assert_eq!(
+        params.iter().collect::<Vec<_>>(),
+        vec![("user", "alice"), ("post", "12"), ("*", "files/a.png")]
+    );
+
+    let map = HashMap::<String, String>::from(&params);
+    assert_eq!(map.get("post").map(String::as_str), Some("12"));
+    assert_eq!(map.get("*").map(String::as_str), Some("files/a.png"));
+
+    let res = router.recognize("/users/bob");
+    let (_, params) = res.route().unwrap();
+    let pairs = Vec::<(String, String)>::from(&params.unwrap());
+    assert_eq!(pairs, vec![("user".to_owned(), "bob".to_owned())]);
+
+    Ok(())
+}
+
+#[test]
+fn params_parse() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/users/:id/files/*", "file")?;
+
+    let res = router.recognize("/users/42/files/a.png");
+    let (_, params) = res.route().unwrap();
+    let params = params.unwrap();
+
+    assert_eq!(params.parse::<u64>("id").unwrap(), 42);
+    assert_eq!(params.parse_at::<u8>(0).unwrap(), 42);
+    assert_eq!(params.parse::<String>("*").unwrap(), "a.png");
+
+    let err = params.parse::<u64>("name").unwrap_err();
+    assert!(err.is_missing());
+    assert_eq!(err.name(), Some("name"));
+    assert_eq!(err.value(), None);
+
+    let err = params.parse_at::<u64>(1).unwrap_err();
+    assert!(err.is_missing());
+    assert_eq!(err.position(), Some(1));
+
+    let err = params.parse::<u64>("*").unwrap_err();
+    assert!(err.is_parse_error());
+    assert_eq!(err.value(), Some("a.png"));
+    assert!(std::error::Error::source(&err).is_some());
+
+    let res = router.recognize("/users/x/files/a.png");
+    let params = res.route().and_then(|(_, params)| params).unwrap();
+    let err = params.parse_at::<u64>(0).unwrap_err();
+    assert!(err.is_parse_error());
+    assert_eq!(err.name(), Some("id"));
+    assert_eq!(err.position(), Some(0));
+    assert_eq!(err.value(), Some("x"));
+    assert_eq!(
+        err.to_string(),
+        "the parameter `id` has an invalid value \"x\": invalid digit found in string"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fallback() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/api/v1/posts", "posts")?;
+    let api = router.add_scope("/api/", "api")?;
+    router.add_scope("/api/v1/", "api_v1")?;
+    router.set_scope_fallback(api, "api_not_found")?;
+    router.set_fallback("not_found")?;
+
+    assert!(router.recognize("/api/v1/posts").fallback().is_none());
+    assert_eq!(
+        router
+            .recognize("/api/v1/users")
+            .fallback()
+            .map(|f| f.data()),
+        Some(&"api_not_found")
+    );
+    assert_eq!(
+        router
+            .recognize("/api/v1/users")
+            .scopes()
+            .map(|s| s.data())
+            .collect::<Vec<_>>(),
+        vec![&"api", &"api_v1"]
+    );
+    assert_eq!(
+        router
+            .recognize("/favicon.ico")
+            .fallback()
+            .map(|f| f.data()),
+        Some(&"not_found")
+    );
+
+    assert!(router.set_fallback("another").is_err());
+    assert!(router.set_scope_fallback(api, "another").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn priority() -> tsukuyomi_router::Result<()> {
+    // (route, route, [(path, expected route)])
+    type Case = (
+        &'static str,
+        &'static str,
+        &'static [(&'static str, &'static str)],
+    );
+
+    let cases: &[Case] = &[
+        // static vs param
+        (
+            "/posts/new",
+            "/posts/:post",
+            &[("/posts/new", "/posts/new"), ("/posts/1", "/posts/:post")],
+        ),
+        // param vs wildcard
+        (
+            "/files/:name",
+            "/files/*",
+            &[("/files/a", "/files/:name"), ("/files/a/b", "/files/*")],
+        ),
+        // wildcard vs wildcard
+        (
+            "/static/*.html",
+            "/static/*/index.html",
+            &[
+                ("/static/a/index.html", "/static/*/index.html"),
+                ("/static/a/about.html", "/static/*.html"),
+            ],
+        ),
+        (
+            "/static/*",
+            "/static/*.html",
+            &[
+                ("/static/a.html", "/static/*.html"),
+                ("/static/a.css", "/static/*"),
+            ],
+        ),
+        // static prefix leading to a dead end
+        (
+            "/posts/new",
+            "/posts/:post/edit",
+            &[("/posts/new/edit", "/posts/:post/edit")],
+        ),
+    ];
+
+    for &(a, b, expected) in cases {
+        for &(first, second) in &[(a, b), (b, a)] {
+            let mut router = Router::new();
+            router.add_route(first, first)?;
+            router.add_route(second, second)?;
+
+            for &(path, route) in expected {
+                assert_eq!(
+                    router.recognize(path).route().map(|(r, _)| *r.data()),
+                    Some(route),
+                    "recognize {:?} with {:?} added before {:?}",
+                    path,
+                    first,
+                    second,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn check_conflicts() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/files/*.html", "html")?;
+    router.add_route("/files/*/index.html", "index")?;
+    router.add_route("/a/:x", "a_param")?;
+    router.add_route("/a/*", "a_wildcard")?;
+    router.add_route("/a/:x/b", "a_param_b")?;
+    router.add_route("/b/:x", "b_param")?;
+    router.add_scope("/b/", "b")?;
+
+    let conflicts = router.check_conflicts();
+    let summary: Vec<_> = conflicts
+        .iter()
+        .map(|c| {
+            let (a, b) = c.routes();
+            (*a.data(), *b.data(), c.example(), *c.winner().data())
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("html", "index", "/files/x/index.html", "index"),
+            ("a_param", "a_wildcard", "/a/x", "a_param"),
+            ("a_wildcard", "a_param_b", "/a/x/b", "a_param_b"),
+        ]
+    );
+    assert_eq!(
+        conflicts[1].to_string(),
+        "`/a/:x` and `/a/*` both match \"/a/x\" (recognized as `/a/:x`)"
+    );
+
+    let mut router = Router::new();
+    router.add_route(r"/a/:x<\d+>", "digits")?;
+    router.add_route("/a/:y", "param")?;
+    let conflicts = router.check_conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].example(), "/a/0");
+    assert_eq!(*conflicts[0].winner().data(), "digits");
+
+    Ok(())
+}
+
+#[test]
+fn versioned() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::VersionSource;
+
+    let mut router = Router::new();
+    router.add_versioned("/users/:id", 1.., "users_v1")?;
+    router.add_versioned("/users/:id", 3..5, "users_v3")?;
+    router.add_versioned("/posts", 2.., "posts")?;
+    router.add_route("/health", "health")?;
+    router.add_route("/v1/status", "status")?;
+
+    assert!(router.add_versioned("/users/:name", 3..=3, "dup").is_err());
+    assert!(router.add_route("/posts", "unversioned").is_err());
+    assert!(router.add_versioned("/health", 1.., "versioned").is_err());
+    assert!(router.add_versioned("/empty", 2..2, "empty").is_err());
+
+    fn recognize(router: &Router<&'static str>, path: &str) -> (Option<&'static str>, Option<u32>) {
+        let res = router.recognize(path);
+        (res.route().map(|(r, _)| *r.data()), res.version())
+    }
+
+    // Without a version source, the latest route is used.
+    assert_eq!(recognize(&router, "/users/1"), (Some("users_v3"), Some(3)));
+    assert_eq!(recognize(&router, "/v1/users/1"), (None, None));
+
+    router.set_version_source(VersionSource::PathPrefix);
+    assert_eq!(
+        recognize(&router, "/v1/users/1"),
+        (Some("users_v1"), Some(1))
+    );
+    assert_eq!(
+        recognize(&router, "/v2/users/1"),
+        (Some("users_v1"), Some(2))
+    );
+    assert_eq!(
+        recognize(&router, "/v4/users/1"),
+        (Some("users_v3"), Some(4))
+    );
+    assert_eq!(
+        recognize(&router, "/v5/users/1"),
+        (Some("users_v1"), Some(5))
+    );
+    assert_eq!(recognize(&router, "/v1/posts"), (None, None));
+    assert_eq!(recognize(&router, "/v2/posts"), (Some("posts"), Some(2)));
+    // the prefix is only stripped for the versioned routes.
+    assert_eq!(recognize(&router, "/v1/health"), (None, None));
+    assert_eq!(recognize(&router, "/health"), (Some("health"), None));
+    assert_eq!(recognize(&router, "/v1/status"), (Some("status"), None));
+    assert_eq!(recognize(&router, "/users/1"), (Some("users_v3"), Some(3)));
+
+    let res = router.recognize("/v2/users/42");
+    let (route, params) = res.route().unwrap();
+    assert_eq!(route.versions(), Some(&(1..=u32::MAX)));
+    assert_eq!(params.unwrap().get(0), Some("42"));
+
+    Ok(())
+}
+
+#[test]
+fn versioned_route_table() -> tsukuyomi_router::Result<()> {
+    let table = "\
+route /users/:id users_v1 versions=1..3
+route /users/:id users_v3 versions=3..
+route /posts posts versions=2..=4
+";
+    let router = Router::from_table(table.as_bytes())?;
+    assert_eq!(
+        router
+            .recognize("/users/1")
+            .route()
+            .map(|(r, _)| r.versions()),
+        Some(Some(&(3..=u32::MAX)))
+    );
+
+    let mut written = vec![];
+    router.write_table(&mut written)?;
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        "\
+route /users/:id users_v1 versions=1..=2
+route /users/:id users_v3 versions=3..
+route /posts posts versions=2..=4
+"
+    );
+
+    for line in &[
+        "route /a a versions=",
+        "route /a a versions=3..1",
+        "route /a a versions=..=",
+        "scope /a a versions=1..",
+    ] {
+        assert!(Router::from_table(line.as_bytes()).is_err(), "{}", line);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn guards() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::{guard, Input};
+
+    let mut router = Router::new();
+    let json = router.add_route("/posts/:post", "json")?;
+    router.add_guard(json, guard::header("content-type", "application/json"))?;
+    let preview = router.add_route("/posts/:post", "preview")?;
+    router.add_guard(preview, |input: &Input<'_>| {
+        input.query_param("preview").is_some()
+    })?;
+    let get = router.add_route("/posts/:post", "get")?;
+    router.set_methods(get, vec!["GET"])?;
+    router.add_route("/posts/*", "catch_all")?;
+
+    let data = |input: Input<'_>| {
+        let recognize = router.recognize_input(&input);
+        (
+            recognize.route().map(|(r, _)| *r.data()),
+            recognize.is_method_not_allowed(),
+        )
+    };
+
+    let input =
+        Input::new("/posts/1").with_header("Content-Type", "application/json; charset=utf-8");
+    assert_eq!(data(input), (Some("json"), false));
+    assert_eq!(
+        data(Input::new("/posts/1").with_query("preview")),
+        (Some("preview"), false)
+    );
+    assert_eq!(data(Input::new("/posts/1")), (Some("get"), false));
+    assert_eq!(
+        data(Input::new("/posts/1").with_method("GET")),
+        (Some("get"), false)
+    );
+    assert_eq!(
+        data(Input::new("/posts/1").with_method("POST")),
+        (Some("catch_all"), false)
+    );
+    assert_eq!(
+        data(Input::new("/posts/1/comments").with_method("POST")),
+        (Some("catch_all"), false)
+    );
+
+    let mut router = Router::new();
+    let get = router.add_route("/users/:id", "get")?;
+    router.set_methods(get, vec!["GET"])?;
+    let recognize = router.recognize_input(&Input::new("/users/1").with_method("DELETE"));
+    assert!(recognize.route().is_none());
+    assert!(recognize.is_method_not_allowed());
+
+    Ok(())
+}
+
+#[test]
+fn hosts() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::Input;
+
+    let mut router = Router::new();
+    let admin = router.add_route("/admin", "admin")?;
+    router.set_host(admin, "admin.example.com")?;
+    let local = router.add_route("/local", "local")?;
+    router.set_host(local, "[::1]")?;
+    let loopback = router.add_route("/loopback", "loopback")?;
+    router.set_host(loopback, "::1")?;
+
+    let matches = |path: &str, host: &str| {
+        let input = Input::new(path).with_method("GET").with_host(host);
+        router.recognize_input(&input).route().is_some()
+    };
+    assert!(matches("/admin", "admin.example.com"));
+    assert!(matches("/admin", "Admin.Example.com:8080"));
+    assert!(!matches("/admin", "www.example.com"));
+    assert!(matches("/local", "[::1]"));
+    assert!(matches("/local", "[::1]:8080"));
+    assert!(!matches("/local", "[::2]:8080"));
+    assert!(matches("/local", "::1"));
+    assert!(matches("/loopback", "[::1]:8080"));
+    assert!(matches("/loopback", "[::1]"));
+    assert!(!matches("/loopback", "[::2]"));
+
+    Ok(())
+}
+
+#[test]
+fn extensions() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/reports/:id", "report")?;
+    router.add_route("/static/robots.txt", "robots")?;
+    router.add_route("/files/*", "files")?;
+    router.add_route("/files/report.json", "report_json")?;
+    router.add_extension("json")?;
+    router.add_extension(".csv")?;
+    router.add_extension("txt")?;
+    router.add_extension("tar.gz")?;
+    router.add_extension("gz")?;
+
+    assert!(router.add_extension("").is_err());
+    assert!(router.add_extension("a/b").is_err());
+    assert_eq!(
+        router.extensions().collect::<Vec<_>>(),
+        vec!["tar.gz", "json", "csv", "txt", "gz"]
+    );
+
+    let recognize = |path| {
+        let res = router.recognize(path);
+        let (route, params) = res.route()?;
+        Some((
+            *route.data(),
+            params.and_then(|p| p.get(0).or_else(|| p.get_wildcard()).map(str::to_owned)),
+            res.extension(),
+        ))
+    };
+
+    assert_eq!(
+        recognize("/reports/42"),
+        Some(("report", Some("42".into()), None))
+    );
+    assert_eq!(
+        recognize("/reports/42.json"),
+        Some(("report", Some("42".into()), Some("json")))
+    );
+    assert_eq!(
+        recognize("/reports/42.csv"),
+        Some(("report", Some("42".into()), Some("csv")))
+    );
+    assert_eq!(
+        recognize("/reports/42.xml"),
+        Some(("report", Some("42.xml".into()), None))
+    );
+    assert_eq!(
+        recognize("/reports/.json"),
+        Some(("report", Some(".json".into()), None))
+    );
+    assert_eq!(
+        recognize("/static/robots.txt"),
+        Some(("robots", None, None))
+    );
+    assert_eq!(
+        recognize("/files/a/b.tar.gz"),
+        Some(("files", Some("a/b".into()), Some("tar.gz")))
+    );
+    assert_eq!(
+        recognize("/files/report.json"),
+        Some(("report_json", None, None))
+    );
+    assert_eq!(
+        recognize("/files/summary.json"),
+        Some(("files", Some("summary".into()), Some("json")))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn query() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::guard;
+
+    let mut router = Router::new();
+    let search = router.add_route("/posts", "search")?;
+    router.add_guard(search, guard::query("q"))?;
+    router.add_route("/posts", "list")?;
+    router.add_route("/posts/:post", "the_post")?;
+
+    let recognize = router.recognize("/posts/12?x=1&y#comments");
+    let (route, params) = recognize.route().unwrap();
+    assert_eq!(*route.data(), "the_post");
+    assert_eq!(params.unwrap().get(0), Some("12"));
+    assert_eq!(
+        recognize.query().collect::<Vec<_>>(),
+        vec![("x", "1"), ("y", "")]
+    );
+    assert_eq!(recognize.query().get("x"), Some("1"));
+
+    let data = |path| router.recognize(path).route().map(|(r, _)| *r.data());
+    assert_eq!(data("/posts?q=rust"), Some("search"));
+    assert_eq!(data("/posts?page=2"), Some("list"));
+    assert_eq!(data("/posts#q"), Some("list"));
+    assert_eq!(router.recognize("/posts").query().count(), 0);
+
+    assert_eq!(
+        router.explain("/posts/12?x=1").outcome(),
+        tsukuyomi_router::explain::Outcome::Matched(
+            router.recognize("/posts/12").route().unwrap().0.id()
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn constrained_params() -> tsukuyomi_router::Result<()> {
+    use tsukuyomi_router::{explain::Step, typed::Route};
+
+    let mut router = Router::new();
+    let archive = router.add_route(r"/archive/:date<\d{4}/\d{2}/\d{2}>", "archive")?;
+    router.add_route(r"/archive/:date<\d{4}/\d{2}/\d{2}>/:slug", "post")?;
+    router.add_route("/archive/*", "catch_all")?;
+
+    assert_eq!(router[archive].param_names().collect::<Vec<_>>(), ["date"]);
+
+    let recognize = router.recognize("/archive/2019/05/01/hello");
+    let (route, params) = recognize.route().unwrap();
+    assert_eq!(*route.data(), "post");
+    let params = params.unwrap();
+    assert_eq!(params.get(0), Some("2019/05/01"));
+    assert_eq!(params.get(1), Some("hello"));
+
+    let data = |path| router.recognize(path).route().map(|(r, _)| *r.data());
+    assert_eq!(data("/archive/2019/05/01"), Some("archive"));
+    assert_eq!(data("/archive/2019/5/1"), Some("catch_all"));
+
+    let explain = router.explain("/archive/2019/05/01");
+    assert!(explain.steps().contains(&Step::Constraint {
+        pattern: r"\d{4}/\d{2}/\d{2}".into(),
+        start: 9,
+        end: 19,
+        matched: true,
+    }));
+
+    let suggested: Vec<_> = router
+        .suggest("/archiv/2019/05/01/hello", 3)
+        .into_iter()
+        .map(|e| e.path())
+        .collect();
+    assert!(suggested.contains(&r"/archive/:date<\d{4}/\d{2}/\d{2}>/:slug"));
+
+    struct Archive;
+    impl Route for Archive {
+        const PATH: &'static str = r"/archive/:date<\d{4}/\d{2}/\d{2}>/:slug";
+        type Params = (String, String);
+    }
+    assert_eq!(
+        Archive::url(&("2019/05/01".into(), "hello".into())),
+        "/archive/2019/05/01/hello"
+    );
+
+    assert!(router.add_route("/archive/:date<(>", "invalid").is_err());
+    assert!(router
+        .add_route("/archive/:date<\\d+>.html", "invalid")
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn segment_matchers() -> tsukuyomi_router::Result<()> {
+    use std::collections::HashSet;
+
+    let tenants: HashSet<String> = vec!["acme".to_owned(), "initech".to_owned()]
+        .into_iter()
+        .collect();
+
+    let mut router = Router::new();
+    router.register_matcher("tenant", move |seg: &str| tenants.contains(seg))?;
+    router.register_matcher("sku", |seg: &str| {
+        seg.len() == 8 && seg.bytes().all(|b| b.is_ascii_alphanumeric())
+    })?;
+    assert!(router.register_matcher("sku", |_: &str| true).is_err());
+    assert!(router.register_matcher("a/b", |_: &str| true).is_err());
+
+    router.add_route("/:tenant<tenant>/products/:code<sku>", "product")?;
+    router.add_route("/:tenant<tenant>/products/:name", "search")?;
+    router.add_route("/:page", "page")?;
+
+    let recognize = router.recognize("/acme/products/AB12CD34");
+    let (route, params) = recognize.route().unwrap();
+    assert_eq!(*route.data(), "product");
+    assert_eq!(route.param_names().collect::<Vec<_>>(), ["tenant", "code"]);
+    let params = params.unwrap();
+    assert_eq!(params.get(0), Some("acme"));
+    assert_eq!(params.get(1), Some("AB12CD34"));
+
+    let data = |path| router.recognize(path).route().map(|(r, _)| *r.data());
+    assert_eq!(data("/initech/products/shoes"), Some("search"));
+    assert_eq!(data("/umbrella/products/AB12CD34"), None);
+    assert_eq!(data("/umbrella"), Some("page"));
+
+    let tree = router.print_tree().to_string();
+    assert!(tree.contains(":tenant<tenant>"));
+    assert!(tree.contains(":code<sku>  [route #0 /:tenant<tenant>/products/:code<sku>]"));
+
+    let mut router = Router::new();
+    router.add_route("/items/:id<digits>", "item")?;
+    assert!(router
+        .register_matcher("digits", |seg: &str| seg
+            .bytes()
+            .all(|b| b.is_ascii_digit()))
+        .is_err());
+    assert!(router.register_matcher("letters", |_: &str| true).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn recognize_bytes() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    router.add_route("/files/:name", "file")?;
+    router.add_route("/files/:name/raw", "raw")?;
+    router.add_route("/static/*", "static")?;
+    router.add_route("/caf\u{e9}/:id", "cafe")?;
+
+    let recognize = router.recognize_bytes(b"/files/\xff\xfe/raw?x=1");
+    let (route, params) = recognize.route().unwrap();
+    assert_eq!(*route.data(), "raw");
+    let params = params.unwrap();
+    assert_eq!(params.get_bytes(0), Some(&b"\xff\xfe"[..]));
+    assert_eq!(params.name_bytes("name"), Some(&b"\xff\xfe"[..]));
+    assert_eq!(params.get(0), None);
+    assert_eq!(params.len(), 1);
+    assert_eq!(params.iter().count(), 0);
+    assert_eq!(params.names().collect::<Vec<_>>(), vec!["name"]);
+    let err = params.parse::<String>("name").unwrap_err();
+    assert!(err.is_parse_error());
+    assert_eq!(err.value(), Some("\u{fffd}\u{fffd}"));
+    assert_eq!(recognize.query().get("x"), Some("1"));
+
+    let recognize = router.recognize_bytes(b"/static/a/\xe9.css");
+    let (_, params) = recognize.route().unwrap();
+    let params = params.unwrap();
+    assert_eq!(params.get_wildcard_bytes(), Some(&b"a/\xe9.css"[..]));
+    assert_eq!(params.get_wildcard(), None);
+    assert_eq!(params.len(), 1);
+    assert_eq!(params.iter().count(), 0);
+    assert_eq!(params.names().collect::<Vec<_>>(), vec!["*"]);
+
+    // a multi-byte character that only shares its first byte with a static segment.
+    assert!(router.recognize_bytes(b"/caf\xc3").route().is_none());
+    assert!(router.recognize_bytes(b"/caf\xc3\xa8/1").route().is_none());
+
+    let recognize = router.recognize_bytes("/caf\u{e9}/42".as_bytes());
+    let (route, params) = recognize.route().unwrap();
+    assert_eq!(*route.data(), "cafe");
+    assert_eq!(params.unwrap().get(0), Some("42"));
+
+    Ok(())
+}
+
+#[test]
+fn owned_match() -> tsukuyomi_router::Result<()> {
+    use std::{sync::Arc, thread};
+
+    let mut router = Router::new();
+    let post = router.add_route("/posts/:post", "the_post")?;
+    let posts = router.add_scope("/posts/", "posts")?;
+    let router = Arc::new(router);
+
+    let owned = router
+        .recognize("/posts/42?page=2")
+        .into_owned_with(&router);
+    let handle = thread::spawn(move || {
+        assert_eq!(owned.route_id(), Some(post));
+        assert_eq!(owned.scope_ids(), [posts]);
+        assert_eq!(owned.params().and_then(|p| p.get(0)), Some("42"));
+        assert_eq!(owned.query().get("page"), Some("2"));
+        assert_eq!(owned.route().map(|r| *r.data()), Some("the_post"));
+        assert_eq!(
+            owned.scopes().map(|s| *s.data()).collect::<Vec<_>>(),
+            ["posts"]
+        );
+    });
+    handle.join().unwrap();
+
+    let owned = router.recognize("/posts/42").into_owned();
+    assert_eq!(owned.route_id(), Some(post));
+    assert!(owned.route().is_none());
+    assert_eq!(owned.scopes().count(), 0);
+
+    let owned = router.recognize("/posts/").into_owned();
+    assert!(owned.route_id().is_none());
+    assert!(owned.params().is_none());
+    assert_eq!(owned.scope_ids(), [posts]);
+
+    Ok(())
+}
+
+#[test]
+fn clone_router() -> tsukuyomi_router::Result<()> {
+    use std::sync::Arc;
+
+    let mut base = Router::new();
+    let users = base.add_route("/users/:id", String::from("users"))?;
+    base.add_route("/posts/:id", String::from("posts"))?;
+
+    let mut tenant = base.clone();
+    let admin = tenant.add_route("/admin", String::from("admin"))?;
+    tenant.set_methods(users, vec!["GET"])?;
+
+    assert!(base.recognize("/admin").route().is_none());
+    assert_eq!(
+        tenant.recognize("/admin").route().map(|(r, _)| r.id()),
+        Some(admin)
+    );
+    assert!(base[users].methods().is_empty());
+    assert_eq!(tenant[users].methods(), ["GET"]);
+
+    // the data is shared until it is modified.
+    assert!(Arc::ptr_eq(
+        &base[users].shared_data(),
+        &tenant[users].shared_data()
+    ));
+    assert!(tenant[users].try_data_mut().is_none());
+    tenant[users].make_data_mut().push_str("_v2");
+    assert_eq!(base[users].data(), "users");
+    assert_eq!(tenant[users].data(), "users_v2");
+    tenant[users].try_data_mut().unwrap().push('!');
+    assert_eq!(tenant[users].data(), "users_v2!");
+
+    Ok(())
+}
+
+#[test]
+fn data_mut_without_clone() -> tsukuyomi_router::Result<()> {
+    struct NotClone(u32);
+
+    let mut router = Router::new();
+    let id = router.add_route("/counter", NotClone(0))?;
+    router[id].try_data_mut().unwrap().0 += 1;
+    assert_eq!(router[id].data().0, 1);
+
+    let shared = router[id].shared_data();
+    assert!(router[id].try_data_mut().is_none());
+    drop(shared);
+    assert!(router[id].try_data_mut().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn builder() {
+    let mut builder = Router::builder();
+    let post = builder.route("/posts/:post", "the_post").name("post").id();
+    builder.route("/posts/new", "new_post").methods(vec!["GET"]);
+    builder.scope("/posts/", "posts");
+    let fallback = builder.fallback("not_found").id();
+    let router = builder.build().unwrap();
+
+    assert_eq!(
+        router.endpoints().map(|e| *e.data()).collect::<Vec<_>>(),
+        vec!["the_post", "new_post", "posts", "not_found"]
+    );
+    assert_eq!(router.endpoint_by_name("post").map(|e| e.id()), Some(post));
+    assert_eq!(
+        router
+            .recognize("/posts/new")
+            .route()
+            .map(|(r, _)| r.methods()),
+        Some(&["GET".to_owned()][..])
+    );
+    assert_eq!(
+        router.recognize("/").fallback().map(|e| e.id()),
+        Some(fallback)
+    );
+
+    // the built router matches the same way as one built with `add_route`.
+    let paths = [r"/a/:x<\d+>", r"/a/:x<[a-z0-9]+>", "/a/b", "/a/*", "/:y/c"];
+    let mut builder = Router::builder();
+    let mut router = Router::new();
+    for &path in &paths {
+        builder.route(path, path);
+        router.add_route(path, path).unwrap();
+    }
+    let built = builder.build().unwrap();
+    for path in &["/a/123", "/a/abc", "/a/b", "/a/b/c", "/a/c", "/x/c"] {
+        let data =
+            |router: &Router<&'static str>| router.recognize(path).route().map(|(r, _)| *r.data());
+        assert_eq!(data(&built), data(&router), "{}", path);
+    }
+    assert_eq!(
+        built.recognize("/a/123").route().map(|(r, _)| *r.data()),
+        Some(r"/a/:x<\d+>")
+    );
+    assert_eq!(
+        built.print_tree().to_string(),
+        router.print_tree().to_string()
+    );
+
+    // the tree is compacted the same way whatever the order of the declarations.
+    let paths = [
+        "/posts/:post",
+        "/*",
+        "/posts/new",
+        "/users",
+        "/posts",
+        "/users/:id/books",
+    ];
+    let print = |paths: &[&'static str]| {
+        let mut builder = Router::builder();
+        for &path in paths {
+            builder.route(path, path);
+        }
+        let tree = builder.build().unwrap().print_tree().to_string();
+        tree.lines()
+            .map(|line| line.split(" #").next().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+    let mut reversed = paths;
+    reversed.reverse();
+    assert_eq!(print(&paths), print(&reversed));
+
+    // the fallback of a scope.
+    let mut builder = Router::builder();
+    let api = builder.scope("/api/", "api").id();
+    let api_fallback = builder.scope_fallback(api, "api_not_found").id();
+    let route = builder.route("/x", "x").id();
+    builder.scope_fallback(route, "invalid");
+    assert_eq!(
+        builder
+            .build()
+            .unwrap_err()
+            .iter()
+            .map(|e| e.index())
+            .collect::<Vec<_>>(),
+        vec![Some(3)]
+    );
+    let mut builder = Router::builder();
+    builder.scope("/api/", "api");
+    builder.scope_fallback(api, "api_not_found");
+    let router = builder.build().unwrap();
+    assert_eq!(
+        router.recognize("/api/unknown").fallback().map(|e| e.id()),
+        Some(api_fallback)
+    );
+
+    // every invalid declaration is reported.
+    let mut builder = Router::builder();
+    builder.route("/posts/:post", "the_post").name("post");
+    builder.route("/posts/seg:ment", "invalid");
+    builder.route("/users", "users").name("post");
+    builder.versioned("/v", 3..3, "empty");
+    builder.extension("a/b");
+    let errors = builder.build().unwrap_err();
+    assert_eq!(
+        errors.iter().map(|e| e.index()).collect::<Vec<_>>(),
+        vec![None, Some(1), Some(2), Some(3)]
+    );
+    assert_eq!(
+        errors[2].to_string(),
+        "entry #2: the endpoint name `post` is already used by entry #0"
+    );
+}
+
+#[test]
+fn scope_params() -> tsukuyomi_router::Result<()> {
+    let mut router = Router::new();
+    let org = router.add_scope("/orgs/:org/", "org")?;
+    let repo = router.add_scope("/orgs/:org/repos/:repo/", "repo")?;
+    router.add_route("/orgs/:org/repos/:repo/issues/:issue", "issue")?;
+    let files = router.add_scope("/files/*", "files")?;
+    router.add_route("/files/*", "file")?;
+
+    let recognize = router.recognize("/orgs/rust-lang/repos/cargo/issues/42");
+    let (scope, params) = recognize.scope().unwrap();
+    assert_eq!(scope.id(), repo);
+    let params = params.unwrap();
+    assert_eq!(params.len(), 2);
+    assert_eq!(
+        params.iter().collect::<Vec<_>>(),
+        vec![("org", "rust-lang"), ("repo", "cargo")]
+    );
+    assert_eq!(params.get(2), None);
+
+    let params = recognize.scope_params(org).unwrap();
+    assert_eq!(params.len(), 1);
+    assert_eq!(params.name("org"), Some("rust-lang"));
+    assert_eq!(params.name("repo"), None);
+
+    assert_eq!(recognize.param_scope("org").map(|s| s.id()), Some(org));
+    assert_eq!(recognize.param_scope("repo").map(|s| s.id()), Some(repo));
+    assert!(recognize.param_scope("issue").is_none());
+    assert!(recognize.param_scope("*").is_none());
+
+    // the parameters of the scope are available without a matching route.
+    let recognize = router.recognize("/orgs/rust-lang/members");
+    assert!(recognize.route().is_none());
+    let (scope, params) = recognize.scope().unwrap();
+    assert_eq!(scope.id(), org);
+    assert_eq!(params.unwrap().name("org"), Some("rust-lang"));
+
+    let recognize = router.recognize("/files/docs/readme.md");
+    let (scope, params) = recognize.scope().unwrap();
+    assert_eq!(scope.id(), files);
+    assert_eq!(params.unwrap().get_wildcard(), Some("docs/readme.md"));
+    assert_eq!(recognize.param_scope("*").map(|s| s.id()), Some(files));
+
+    Ok(())
+}